@@ -1,5 +1,5 @@
 use communication::registration::{RegistrationService, RegistrationRequest, AgentConfig};
-use file_monitor::FileMonitor;
+use file_monitor::{FileMonitor, MonitorConfig, WatchRoot};
 use std::io::{self, Write};
 use std::path::Path;
 
@@ -89,17 +89,35 @@ fn get_registration_info() -> Result<RegistrationRequest, Box<dyn std::error::Er
     Ok(request)
 }
 
+/// File monitor settings from `file_monitor.toml` if present. Without
+/// configured roots the agent watches `file_monitor/tests` as before.
+fn load_monitor_config() -> Result<MonitorConfig, Box<dyn std::error::Error + Send + Sync>> {
+    let path = Path::new("file_monitor.toml");
+    let mut config = if path.exists() {
+        MonitorConfig::load(path).map_err(|e| e.to_string())?
+    } else {
+        MonitorConfig::default()
+    };
+
+    if config.roots.is_empty() {
+        config.roots.push(WatchRoot::new("file_monitor/tests"));
+    }
+    Ok(config)
+}
+
 async fn start_monitors(config: &AgentConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let comm = file_monitor::Communication::new(
         config.device_id.clone(),
         "https://backend-security-solution.onrender.com/api/alerts".to_string()
     );
     
-    let file_monitor = FileMonitor::new(comm);
-    let path = Path::new("file_monitor/tests");
+    let monitor_config = load_monitor_config()?;
+    let file_monitor = FileMonitor::new(comm)
+        .with_config(monitor_config)
+        .with_policy_group(&config.registration_data.policy_group);
     
     let file_monitor_handle = tokio::spawn(async move {
-        if let Err(e) = file_monitor.start_monitoring(path).await {
+        if let Err(e) = file_monitor.start_monitoring_roots().await {
             eprintln!("File monitor error: {}", e);
         }
     });
@@ -1,5 +1,4 @@
 pub mod registration; 
-use std::error::Error;
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 
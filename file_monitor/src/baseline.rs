@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::scope::FileScope;
+
+/// Crawl of every file already under a watched root, so files that existed
+/// before the agent started are scanned too.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BaselineConfig {
+    pub enabled: bool,
+    /// Crawl again this long after the previous crawl finished. Without it
+    /// the crawl runs once per start.
+    pub interval_secs: Option<u64>,
+    /// Files scanned per second; 0 scans as fast as possible.
+    pub files_per_second: u32,
+    /// Progress is printed and saved every this many files.
+    pub progress_every: usize,
+    /// Where crawl state is kept so an interrupted crawl resumes after a
+    /// restart instead of starting over.
+    pub state_dir: PathBuf,
+}
+
+impl Default for BaselineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: None,
+            files_per_second: 20,
+            progress_every: 500,
+            state_dir: PathBuf::from("baseline_state"),
+        }
+    }
+}
+
+impl BaselineConfig {
+    /// Pause between files needed to stay under `files_per_second`.
+    pub fn throttle(&self) -> Option<Duration> {
+        (self.files_per_second > 0).then(|| Duration::from_secs(1) / self.files_per_second)
+    }
+
+    fn state_path(&self, root: &Path) -> PathBuf {
+        let digest = Sha256::digest(root.to_string_lossy().as_bytes());
+        let name: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+        self.state_dir.join(format!("{}.json", name))
+    }
+}
+
+/// Persisted progress of the latest crawl of one root.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BaselineState {
+    pub root: PathBuf,
+    pub started_at: DateTime<Utc>,
+    /// Files are crawled in path order, so everything up to and including
+    /// this path is done.
+    pub last_completed: Option<PathBuf>,
+    pub files_scanned: u64,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl BaselineState {
+    fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            started_at: Utc::now(),
+            last_completed: None,
+            files_scanned: 0,
+            completed_at: None,
+        }
+    }
+
+    /// The saved state for `root`, if any.
+    pub fn load(root: &Path, config: &BaselineConfig) -> Option<Self> {
+        let text = std::fs::read_to_string(config.state_path(root)).ok()?;
+        serde_json::from_str::<Self>(&text).ok().filter(|state| state.root == root)
+    }
+
+    fn save(&self, config: &BaselineConfig) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(&config.state_dir)?;
+        std::fs::write(config.state_path(&self.root), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// How long to wait before crawling `root`, or `None` when no crawl is due
+/// in this run. An unfinished crawl is always resumed straight away.
+pub fn next_crawl_in(root: &Path, config: &BaselineConfig, first_run: bool) -> Option<Duration> {
+    let completed_at = match BaselineState::load(root, config) {
+        Some(state) => state.completed_at,
+        None => return Some(Duration::ZERO),
+    };
+    let Some(completed_at) = completed_at else {
+        return Some(Duration::ZERO);
+    };
+
+    match config.interval_secs {
+        Some(interval) => {
+            let due = completed_at + chrono::Duration::seconds(interval as i64);
+            Some((due - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+        }
+        None => first_run.then_some(Duration::ZERO),
+    }
+}
+
+/// One pass over a root: hands out files in path order and records each
+/// one as it is finished.
+pub struct BaselineCrawl {
+    config: BaselineConfig,
+    state: BaselineState,
+    pending: std::vec::IntoIter<PathBuf>,
+    total: usize,
+    done: usize,
+}
+
+impl BaselineCrawl {
+    /// Lists the files under `root`, skipping those an interrupted crawl
+    /// already finished.
+    pub fn start(root: &Path, config: &BaselineConfig) -> Self {
+        Self::start_scoped(root, config, &FileScope::default())
+    }
+
+    /// Like `start`, leaving out ignored directories and files `scope`
+    /// does not cover.
+    pub fn start_scoped(root: &Path, config: &BaselineConfig, scope: &FileScope) -> Self {
+        let state = match BaselineState::load(root, config) {
+            Some(state) if state.completed_at.is_none() => {
+                println!("Resuming baseline scan of {:?} after {:?}", root, state.last_completed);
+                state
+            }
+            _ => BaselineState::new(root),
+        };
+
+        let mut files = Vec::new();
+        list_files(root, scope, &mut files);
+        files.sort();
+        let done = match &state.last_completed {
+            Some(last) => files.partition_point(|file| file <= last),
+            None => 0,
+        };
+        let total = files.len();
+
+        Self {
+            config: config.clone(),
+            state,
+            pending: files.split_off(done).into_iter(),
+            total,
+            done,
+        }
+    }
+
+    pub fn next_file(&mut self) -> Option<PathBuf> {
+        self.pending.next()
+    }
+
+    /// Records `path` as scanned, printing and saving progress every
+    /// `progress_every` files.
+    pub fn completed(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        self.done += 1;
+        self.state.files_scanned += 1;
+        self.state.last_completed = Some(path.to_path_buf());
+
+        if self.config.progress_every > 0 && self.done.is_multiple_of(self.config.progress_every) {
+            println!("Baseline scan of {:?}: {}", self.state.root, self.progress());
+            self.state.save(&self.config)?;
+        }
+        Ok(())
+    }
+
+    /// `done/total files (percent)`.
+    pub fn progress(&self) -> String {
+        let percent = if self.total == 0 { 100.0 } else { self.done as f64 * 100.0 / self.total as f64 };
+        format!("{}/{} files ({:.1}%)", self.done, self.total, percent)
+    }
+
+    pub fn finish(mut self) -> Result<BaselineState, Box<dyn Error>> {
+        self.state.completed_at = Some(Utc::now());
+        self.state.save(&self.config)?;
+        println!("Baseline scan of {:?} finished: {}", self.state.root, self.progress());
+        Ok(self.state)
+    }
+}
+
+/// Regular files under `dir` in `scope`, without following symlinks.
+/// Unreadable directories are reported and skipped.
+fn list_files(dir: &Path, scope: &FileScope, files: &mut Vec<PathBuf>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Baseline scan skipping {:?}: {}", dir, e);
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() && scope.allows_dir(&entry.path()) => list_files(&entry.path(), scope, files),
+            Ok(kind) if kind.is_file() && scope.allows_file(&entry.path()) => files.push(entry.path()),
+            _ => {}
+        }
+    }
+}
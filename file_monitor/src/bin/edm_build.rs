@@ -0,0 +1,37 @@
+use file_monitor::EdmIndex;
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+
+const USAGE: &str = "usage: edm_build <records.csv> <index.json> --salt <salt> [--min-fields <n>]";
+
+/// Builds an Exact Data Match index from a CSV of sensitive records. Only
+/// salted hashes are written, so the index can be shipped to endpoints.
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut paths = Vec::new();
+    let mut salt = None;
+    let mut min_fields = 2;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--salt" => salt = args.next(),
+            "--min-fields" => min_fields = args.next().ok_or(USAGE)?.parse()?,
+            _ => paths.push(arg),
+        }
+    }
+    let (Some(salt), [csv, output]) = (salt, paths.as_slice()) else {
+        return Err(USAGE.into());
+    };
+
+    let index = EdmIndex::build(File::open(csv)?, &salt, min_fields)?;
+    index.save(Path::new(output))?;
+    println!(
+        "Indexed {} records ({} fields, {} required) into {}",
+        index.record_count(),
+        index.fields.len(),
+        index.min_fields,
+        output
+    );
+    Ok(())
+}
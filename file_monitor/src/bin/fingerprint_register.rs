@@ -0,0 +1,50 @@
+use file_monitor::{ContentScanner, FingerprintIndex};
+use std::error::Error;
+use std::path::Path;
+
+const USAGE: &str = "usage: fingerprint_register <index.json> <document>... [--shingle-words <n>] [--min-similarity <0-1>]";
+
+/// Adds confidential documents to a fingerprint index, creating it if it
+/// does not exist yet. Only shingle hashes are stored.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut paths = Vec::new();
+    let mut shingle_words = None;
+    let mut min_similarity = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--shingle-words" => shingle_words = Some(args.next().ok_or(USAGE)?.parse()?),
+            "--min-similarity" => min_similarity = Some(args.next().ok_or(USAGE)?.parse()?),
+            _ => paths.push(arg),
+        }
+    }
+    let [output, documents @ ..] = paths.as_slice() else {
+        return Err(USAGE.into());
+    };
+    if documents.is_empty() {
+        return Err(USAGE.into());
+    }
+
+    let output = Path::new(output);
+    let mut index = if output.exists() { FingerprintIndex::load(output)? } else { FingerprintIndex::default() };
+    if let Some(words) = shingle_words {
+        if !index.documents().is_empty() && words != index.shingle_words {
+            return Err("--shingle-words cannot change once documents are registered".into());
+        }
+        index.shingle_words = words;
+    }
+    if let Some(similarity) = min_similarity {
+        index.min_similarity = similarity;
+    }
+
+    let scanner = ContentScanner::new();
+    for document in documents {
+        index.register_file(&scanner, Path::new(document)).await?;
+        let registered = index.documents().last().map(|d| d.shingle_count).unwrap_or(0);
+        println!("Registered {} ({} shingles)", document, registered);
+    }
+    index.save(output)?;
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use file_monitor::{Quarantine, QuarantineConfig};
+use std::error::Error;
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: quarantine <store-dir> list | quarantine <store-dir> restore <id>";
+
+/// Lists the files held in a quarantine store, or puts one back where it
+/// was found.
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (dir, command) = match args.as_slice() {
+        [dir, command @ ..] => (PathBuf::from(dir), command),
+        _ => return Err(USAGE.into()),
+    };
+    let store = Quarantine::new(QuarantineConfig { dir, ..Default::default() });
+
+    match command {
+        [list] if list == "list" => {
+            for manifest in store.list()? {
+                let types: Vec<&str> = manifest.findings.iter().map(|alert| alert.pattern_type.as_str()).collect();
+                println!(
+                    "{}  {}  {} bytes  [{}]",
+                    manifest.id,
+                    manifest.original_path.display(),
+                    manifest.size,
+                    types.join(", ")
+                );
+            }
+        }
+        [restore, id] if restore == "restore" => {
+            let manifest = store.restore(id)?;
+            println!("Restored {} to {}", manifest.id, manifest.original_path.display());
+        }
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
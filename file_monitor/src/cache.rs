@@ -0,0 +1,325 @@
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use crate::Alert;
+
+/// On-disk record of what each file contained when it was last scanned, so
+/// files that have not changed since are not scanned again.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    pub path: PathBuf,
+    /// Changed entries are written out after this many changes, and when a
+    /// batch of events or a baseline crawl finishes.
+    pub flush_every: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: PathBuf::from("scan_cache.json"),
+            flush_every: 100,
+        }
+    }
+}
+
+/// Size and modification time, compared before hashing a file's contents.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> std::io::Result<Self> {
+        let metadata = std::fs::metadata(path)?;
+        Ok(Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+/// The last scan of one path.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheEntry {
+    pub stamp: FileStamp,
+    /// Hex SHA-256 of the file's bytes.
+    pub content_hash: String,
+    /// `FileMonitor::detector_version` at the time of the scan.
+    pub detector_version: String,
+    /// Alerts the scan raised; empty for a clean file.
+    pub alerts: Vec<Alert>,
+    pub scanned_at: DateTime<Utc>,
+}
+
+/// What a file needs before its alerts are known.
+#[derive(Debug)]
+pub enum CacheCheck {
+    /// Same contents and detectors as the last scan of this path.
+    Unchanged,
+    /// Another path with identical contents was scanned by the same
+    /// detectors; its alerts apply here too.
+    Duplicate { of: PathBuf, content_hash: String, alerts: Vec<Alert> },
+    /// New or changed contents, or the detectors changed since.
+    Changed { content_hash: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct CacheFile {
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+/// One line of the change log kept beside the cache.
+#[derive(Debug, Serialize, Deserialize)]
+struct LoggedEntry {
+    path: PathBuf,
+    entry: CacheEntry,
+}
+
+/// Scan results by path. Changes are appended to a log beside the cache
+/// file, which is only rewritten in full once the log outgrows it, so
+/// saving costs the same per change however large the cache is.
+pub struct ScanCache {
+    path: PathBuf,
+    entries: HashMap<PathBuf, CacheEntry>,
+    /// Paths of entries by content hash, for spotting copies.
+    by_hash: HashMap<String, PathBuf>,
+    /// Paths changed since they were last saved, with when they changed.
+    changed: HashMap<PathBuf, u64>,
+    changes: u64,
+    /// Lines in the log, and entries dropped since the cache was last
+    /// rewritten, which only a rewrite removes.
+    logged: usize,
+    dropped: usize,
+}
+
+impl ScanCache {
+    /// Reads the cache at `path` and its change log, dropping entries for
+    /// files that no longer exist. A missing cache starts with just the log,
+    /// and an unreadable one empty after a warning.
+    pub fn load(path: &Path) -> Self {
+        let (mut file, readable) = match std::fs::read_to_string(path) {
+            Ok(text) => match serde_json::from_str::<CacheFile>(&text) {
+                Ok(file) => (file, true),
+                Err(e) => {
+                    eprintln!("Ignoring unreadable scan cache {:?}: {}", path, e);
+                    (CacheFile::default(), false)
+                }
+            },
+            Err(_) => (CacheFile::default(), true),
+        };
+        let mut logged = 0;
+        let log = readable.then(|| std::fs::read_to_string(log_path(path)).ok()).flatten();
+        if let Some(log) = log {
+            // A line cut short by a crash ends the log
+            for line in log.lines().map_while(|line| serde_json::from_str::<LoggedEntry>(line).ok()) {
+                file.entries.insert(line.path, line.entry);
+                logged += 1;
+            }
+        }
+
+        let mut cache = Self {
+            path: path.to_path_buf(),
+            entries: HashMap::new(),
+            by_hash: HashMap::new(),
+            changed: HashMap::new(),
+            changes: 0,
+            logged,
+            dropped: 0,
+        };
+        for (file_path, entry) in file.entries {
+            if file_path.is_file() {
+                cache.by_hash.insert(entry.content_hash.clone(), file_path.clone());
+                cache.entries.insert(file_path, entry);
+            } else {
+                cache.dropped += 1;
+            }
+        }
+        cache
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&CacheEntry> {
+        self.entries.get(path)
+    }
+
+    /// Compares `path` with its last scan. The contents are only hashed when
+    /// size or modification time differ, and a file that was merely touched
+    /// counts as unchanged.
+    pub fn check(&mut self, path: &Path, stamp: FileStamp, detector_version: &str) -> Result<CacheCheck, Box<dyn Error>> {
+        if self.is_unchanged(path, stamp, detector_version) {
+            return Ok(CacheCheck::Unchanged);
+        }
+        let content_hash = hash_file(path)?;
+        Ok(self.check_content(path, stamp, content_hash, detector_version))
+    }
+
+    /// Whether `path` was last scanned by the same detectors with the same
+    /// size and modification time, so there is no need to hash it.
+    pub fn is_unchanged(&self, path: &Path, stamp: FileStamp, detector_version: &str) -> bool {
+        self.entries
+            .get(path)
+            .is_some_and(|entry| entry.detector_version == detector_version && entry.stamp == stamp)
+    }
+
+    /// The rest of `check`, given the hash of the file's contents. Split out
+    /// so the file can be hashed without holding the cache.
+    pub fn check_content(&mut self, path: &Path, stamp: FileStamp, content_hash: String, detector_version: &str) -> CacheCheck {
+        let current = self.entries.get(path).filter(|entry| entry.detector_version == detector_version);
+        if current.is_some_and(|entry| entry.content_hash == content_hash) {
+            if let Some(entry) = self.entries.get_mut(path) {
+                entry.stamp = stamp;
+                self.changed(path);
+            }
+            return CacheCheck::Unchanged;
+        }
+
+        let copy = self
+            .by_hash
+            .get(&content_hash)
+            .filter(|&other| other != path)
+            .and_then(|other| Some((other, self.entries.get(other)?)))
+            .filter(|(_, entry)| entry.content_hash == content_hash && entry.detector_version == detector_version);
+        match copy {
+            Some((other, entry)) => CacheCheck::Duplicate {
+                of: other.clone(),
+                content_hash,
+                alerts: entry.alerts.clone(),
+            },
+            None => CacheCheck::Changed { content_hash },
+        }
+    }
+
+    /// Records the result of scanning `path`.
+    pub fn insert(&mut self, path: &Path, stamp: FileStamp, content_hash: String, detector_version: &str, alerts: Vec<Alert>) {
+        self.by_hash.insert(content_hash.clone(), path.to_path_buf());
+        self.entries.insert(path.to_path_buf(), CacheEntry {
+            stamp,
+            content_hash,
+            detector_version: detector_version.to_string(),
+            alerts,
+            scanned_at: Utc::now(),
+        });
+        self.changed(path);
+    }
+
+    fn changed(&mut self, path: &Path) {
+        self.changes += 1;
+        self.changed.insert(path.to_path_buf(), self.changes);
+    }
+
+    /// Changed entries not yet written to disk.
+    pub fn dirty(&self) -> usize {
+        self.changed.len() + self.dropped
+    }
+
+    pub fn save(&mut self) -> Result<(), Box<dyn Error>> {
+        let snapshot = self.snapshot();
+        snapshot.write()?;
+        self.saved(&snapshot);
+        Ok(())
+    }
+
+    /// Copies what needs saving so it can be written out without holding
+    /// the cache: the changed entries, or every entry once the log would
+    /// outgrow the cache or dropped entries need removing.
+    pub fn snapshot(&self) -> CacheSnapshot {
+        let rewrite = self.dropped > 0 || self.logged + self.changed.len() > self.entries.len();
+        let entries = if rewrite {
+            self.entries.clone()
+        } else {
+            self.changed
+                .keys()
+                .filter_map(|path| Some((path.clone(), self.entries.get(path)?.clone())))
+                .collect()
+        };
+        CacheSnapshot {
+            path: self.path.clone(),
+            file: CacheFile { entries },
+            rewrite,
+            changes: self.changes,
+        }
+    }
+
+    /// Marks what a written `snapshot` held as saved. Entries changed since
+    /// it was taken stay dirty.
+    pub fn saved(&mut self, snapshot: &CacheSnapshot) {
+        self.changed.retain(|_, changed| *changed > snapshot.changes);
+        if snapshot.rewrite {
+            self.logged = 0;
+            self.dropped = 0;
+        } else {
+            self.logged += snapshot.file.entries.len();
+        }
+    }
+}
+
+/// What a cache needs saving at one point in time, taken by
+/// `ScanCache::snapshot`.
+pub struct CacheSnapshot {
+    path: PathBuf,
+    file: CacheFile,
+    /// Whether `file` holds every entry, to replace the cache and its log,
+    /// rather than changes to append to the log.
+    rewrite: bool,
+    changes: u64,
+}
+
+impl CacheSnapshot {
+    pub fn write(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        if !self.rewrite {
+            let mut lines = String::new();
+            for (path, entry) in &self.file.entries {
+                lines += &serde_json::to_string(&LoggedEntry { path: path.clone(), entry: entry.clone() })?;
+                lines.push('\n');
+            }
+            let mut log = std::fs::OpenOptions::new().create(true).append(true).open(log_path(&self.path))?;
+            log.write_all(lines.as_bytes())?;
+            return Ok(());
+        }
+
+        // Written beside the cache and renamed over it, so a crash mid-write
+        // leaves the previous cache intact. A crash before the log is removed
+        // replays older entries, which at worst rescans their files.
+        let temp = self.path.with_extension("json.partial");
+        std::fs::write(&temp, serde_json::to_string(&self.file)?)?;
+        std::fs::rename(&temp, &self.path)?;
+        match std::fs::remove_file(log_path(&self.path)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn log_path(path: &Path) -> PathBuf {
+    path.with_extension("json.log")
+}
+
+/// Hex SHA-256 of `bytes`.
+pub(crate) fn digest(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+pub(crate) fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
@@ -0,0 +1,176 @@
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+use crate::baseline::BaselineConfig;
+use crate::cache::CacheConfig;
+use crate::debounce::DebounceConfig;
+use crate::detectors::Confidence;
+use crate::quarantine::QuarantineConfig;
+use crate::regional::DetectorPack;
+use crate::workers::WorkerConfig;
+use crate::redaction::RedactionConfig;
+use crate::scope::{ScopeConfig, WatchRoot};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MonitorConfig {
+    /// Directories `FileMonitor::start_monitoring_roots` watches.
+    pub roots: Vec<WatchRoot>,
+    pub scope: ScopeConfig,
+    /// Characters of surrounding text kept on each side of a match.
+    pub context_chars: usize,
+    /// How matched values are masked before they leave the scanner.
+    pub redaction: RedactionConfig,
+    pub scanner: ScannerConfig,
+    pub decoding: DecodingConfig,
+    /// Matches below this confidence are dropped before alerting.
+    pub min_confidence: Confidence,
+    /// A detector only alerts once it matches at least this often in a file.
+    pub min_match_count: usize,
+    /// Regional detector packs enabled for each policy group, e.g.
+    /// `{"finance-dept": ["india", "eu"]}`.
+    pub policy_groups: BTreeMap<String, Vec<DetectorPack>>,
+    /// Bytes from the start of a file evaluated as raw content by YARA rules,
+    /// at most `scanner.text.max_bytes_per_file`.
+    pub yara_max_bytes: u64,
+    pub baseline: BaselineConfig,
+    pub debounce: DebounceConfig,
+    pub cache: CacheConfig,
+    pub workers: WorkerConfig,
+    pub quarantine: QuarantineConfig,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            roots: Vec::new(),
+            scope: ScopeConfig::default(),
+            context_chars: 20,
+            redaction: RedactionConfig::default(),
+            scanner: ScannerConfig::default(),
+            decoding: DecodingConfig::default(),
+            min_confidence: Confidence::Low,
+            min_match_count: 1,
+            policy_groups: BTreeMap::new(),
+            yara_max_bytes: 64 * 1024 * 1024,
+            baseline: BaselineConfig::default(),
+            debounce: DebounceConfig::default(),
+            cache: CacheConfig::default(),
+            workers: WorkerConfig::default(),
+            quarantine: QuarantineConfig::default(),
+        }
+    }
+}
+
+impl MonitorConfig {
+    /// Reads a `.toml`, `.yaml`, `.yml` or `.json` configuration file.
+    /// Settings it leaves out keep their defaults.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let config = Self::parse(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        config.redaction.validate().map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(config)
+    }
+
+    /// The settings that change what a scan reports, for
+    /// `FileMonitor::detector_version`. Roots, scope, workers and the like
+    /// only decide which files are scanned and when.
+    pub fn detection_settings(&self) -> String {
+        serde_json::json!({
+            "context_chars": self.context_chars,
+            "redaction": self.redaction,
+            "scanner": self.scanner,
+            "decoding": self.decoding,
+            "min_confidence": self.min_confidence,
+            "min_match_count": self.min_match_count,
+            "yara_max_bytes": self.yara_max_bytes,
+        })
+        .to_string()
+    }
+
+    fn parse(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default().to_ascii_lowercase();
+        match extension.as_str() {
+            "toml" => toml::from_str(&text).map_err(|e| e.to_string()),
+            "yaml" | "yml" => serde_yaml::from_str(&text).map_err(|e| e.to_string()),
+            "json" => serde_json::from_str(&text).map_err(|e| e.to_string()),
+            _ => Err("configuration files must end in .toml, .yaml, .yml or .json".to_string()),
+        }
+    }
+}
+
+/// Base64, hex and percent-encoded blobs are decoded and scanned as well.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DecodingConfig {
+    /// Layers of encoding peeled off one blob; 0 turns decoding off.
+    pub max_depth: usize,
+    /// Shortest base64 or hex blob worth decoding, in characters.
+    pub min_blob_len: usize,
+}
+
+impl Default for DecodingConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            min_blob_len: 16,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ScannerConfig {
+    pub archive: ArchiveLimits,
+    pub text: TextLimits,
+}
+
+/// Limits applied while expanding archives. Tripping any of them stops the
+/// expansion and raises an archive bomb finding instead.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ArchiveLimits {
+    /// How many archives deep to descend, counting the file itself.
+    pub max_depth: usize,
+    /// Total decompressed bytes across every nested entry of one file.
+    pub max_expanded_bytes: u64,
+    /// Total entries across every nested archive of one file.
+    pub max_entries: usize,
+    /// Largest acceptable decompressed-to-compressed size ratio for an entry.
+    pub max_compression_ratio: f64,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 5,
+            max_expanded_bytes: 1024 * 1024 * 1024,
+            max_entries: 10_000,
+            max_compression_ratio: 100.0,
+        }
+    }
+}
+
+/// Limits applied while streaming plain text files.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TextLimits {
+    /// Bytes read and scanned at a time.
+    pub chunk_bytes: usize,
+    /// Overlap between consecutive chunks when a detector's longest match is
+    /// unbounded, and the cap on the overlap otherwise.
+    pub max_overlap_bytes: usize,
+    /// Bytes of a single file scanned before the rest is skipped.
+    pub max_bytes_per_file: u64,
+}
+
+impl Default for TextLimits {
+    fn default() -> Self {
+        Self {
+            chunk_bytes: 1024 * 1024,
+            max_overlap_bytes: 4096,
+            max_bytes_per_file: 1024 * 1024 * 1024,
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How file events are coalesced before a scan.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DebounceConfig {
+    /// A path is scanned once no event has arrived for it for this long.
+    pub quiet_ms: u64,
+    /// Size and modification time must then stay unchanged for this long,
+    /// so a file still being written is not scanned half way.
+    pub settle_ms: u64,
+    /// Scan anyway after this long, for files such as logs that never go
+    /// quiet.
+    pub max_wait_ms: u64,
+    /// Skip editor swap files, Office lock files, partial downloads and
+    /// similar short-lived files.
+    pub skip_temp_files: bool,
+}
+
+impl Default for DebounceConfig {
+    fn default() -> Self {
+        Self {
+            quiet_ms: 500,
+            settle_ms: 250,
+            max_wait_ms: 10_000,
+            skip_temp_files: true,
+        }
+    }
+}
+
+/// Whether `path` looks like a temporary file an editor, office suite or
+/// browser creates while saving or downloading.
+pub fn is_temp_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let lower = name.to_ascii_lowercase();
+
+    const SUFFIXES: [&str; 10] = [
+        "~", ".swp", ".swo", ".swx", ".tmp", ".temp", ".part", ".crdownload", ".download", ".partial",
+    ];
+    const PREFIXES: [&str; 4] = [
+        ".#",           // Emacs lock files
+        "~$",           // Microsoft Office owner files
+        ".~lock.",      // LibreOffice lock files
+        ".goutputstream-",
+    ];
+
+    SUFFIXES.iter().any(|suffix| lower.ends_with(suffix))
+        || PREFIXES.iter().any(|prefix| lower.starts_with(prefix))
+        || (name.starts_with('#') && name.ends_with('#'))
+        // Vim probes whether a directory is writable with this file
+        || name == "4913"
+}
+
+struct Pending {
+    first_event: Instant,
+    last_event: Instant,
+    /// Size and modification time when the quiet period ended, and when
+    /// that was checked.
+    checked: Option<((u64, Option<SystemTime>), Instant)>,
+}
+
+/// Collects file events and hands each path out once its events have
+/// stopped and its contents have settled.
+pub struct Debouncer {
+    config: DebounceConfig,
+    pending: HashMap<PathBuf, Pending>,
+}
+
+impl Debouncer {
+    pub fn new(config: DebounceConfig) -> Self {
+        Self {
+            config,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Records an event for `path`. Returns false if the path is ignored.
+    pub fn event(&mut self, path: PathBuf, now: Instant) -> bool {
+        if self.config.skip_temp_files && is_temp_file(&path) {
+            return false;
+        }
+        let pending = self.pending.entry(path).or_insert(Pending {
+            first_event: now,
+            last_event: now,
+            checked: None,
+        });
+        pending.last_event = now;
+        pending.checked = None;
+        true
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// When `ready` next has something to do.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending
+            .values()
+            .map(|pending| {
+                let step = match pending.checked {
+                    Some((_, at)) => at + self.settle(),
+                    None => pending.last_event + self.quiet(),
+                };
+                step.min(pending.first_event + self.max_wait())
+            })
+            .min()
+    }
+
+    /// Paths ready to scan at `now`. Paths that no longer exist or are not
+    /// regular files are dropped.
+    pub fn ready(&mut self, now: Instant) -> Vec<PathBuf> {
+        self.ready_at_most(now, usize::MAX)
+    }
+
+    /// Like `ready`, but hands out at most `limit` paths. The rest stay
+    /// pending for a later call.
+    pub fn ready_at_most(&mut self, now: Instant, limit: usize) -> Vec<PathBuf> {
+        let (quiet, settle, max_wait) = (self.quiet(), self.settle(), self.max_wait());
+        let mut ready = Vec::new();
+
+        self.pending.retain(|path, pending| {
+            if ready.len() >= limit {
+                return true;
+            }
+            let overdue = now >= pending.first_event + max_wait;
+            if !overdue && now < pending.last_event + quiet {
+                return true;
+            }
+            let Some(stat) = file_stat(path) else {
+                return false;
+            };
+
+            match pending.checked {
+                _ if overdue => {}
+                Some((previous, at)) if previous == stat && now >= at + settle => {}
+                Some((previous, _)) if previous == stat => return true,
+                _ => {
+                    pending.checked = Some((stat, now));
+                    return true;
+                }
+            }
+            ready.push(path.clone());
+            false
+        });
+
+        ready.sort();
+        ready
+    }
+
+    fn quiet(&self) -> Duration {
+        Duration::from_millis(self.config.quiet_ms)
+    }
+
+    fn settle(&self) -> Duration {
+        Duration::from_millis(self.config.settle_ms)
+    }
+
+    fn max_wait(&self) -> Duration {
+        Duration::from_millis(self.config.max_wait_ms)
+    }
+}
+
+fn file_stat(path: &Path) -> Option<(u64, Option<SystemTime>)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    metadata.is_file().then(|| (metadata.len(), metadata.modified().ok()))
+}
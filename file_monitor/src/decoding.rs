@@ -0,0 +1,113 @@
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+use std::sync::OnceLock;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PayloadEncoding {
+    Base64,
+    Hex,
+    Url,
+}
+
+/// Text decoded from an encoded blob. `start..end` is the blob's position in
+/// the text it was found in; for nested payloads that is the outermost blob.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedPayload {
+    pub start: usize,
+    pub end: usize,
+    /// Encodings peeled off, outermost first.
+    pub chain: Vec<PayloadEncoding>,
+    pub text: String,
+}
+
+struct Patterns {
+    base64: Regex,
+    hex: Regex,
+    url: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        base64: Regex::new(r"[A-Za-z0-9+/_-]{16,}={0,2}").unwrap(),
+        hex: Regex::new(r"\b(?:[0-9A-Fa-f]{2}){8,}\b").unwrap(),
+        url: Regex::new(r"[^\s%]*(?:%[0-9A-Fa-f]{2}[^\s%]*)+").unwrap(),
+    })
+}
+
+/// Finds plausible base64, hex and percent-encoded blobs in `text` and
+/// decodes them, then looks for further blobs inside what was decoded,
+/// peeling off at most `max_depth` layers. Blobs that do not decode to
+/// printable text are ignored.
+pub fn decode_payloads(text: &str, max_depth: usize, min_len: usize) -> Vec<DecodedPayload> {
+    let mut payloads = Vec::new();
+    if max_depth == 0 {
+        return payloads;
+    }
+
+    for (encoding, start, end, decoded) in decode_layer(text, min_len) {
+        let nested = decode_payloads(&decoded, max_depth - 1, min_len);
+        payloads.push(DecodedPayload {
+            start,
+            end,
+            chain: vec![encoding],
+            text: decoded,
+        });
+        payloads.extend(nested.into_iter().map(|inner| DecodedPayload {
+            start,
+            end,
+            chain: std::iter::once(encoding).chain(inner.chain).collect(),
+            text: inner.text,
+        }));
+    }
+
+    payloads
+}
+
+fn decode_layer(text: &str, min_len: usize) -> Vec<(PayloadEncoding, usize, usize, String)> {
+    let patterns = patterns();
+    let mut decoded = Vec::new();
+
+    // Hex digits are also valid base64, so hex gets first claim on a blob
+    for m in patterns.hex.find_iter(text) {
+        if m.len() >= min_len {
+            if let Some(plain) = hex::decode(m.as_str()).ok().and_then(printable) {
+                decoded.push((PayloadEncoding::Hex, m.start(), m.end(), plain));
+            }
+        }
+    }
+    for m in patterns.base64.find_iter(text) {
+        let claimed = decoded.iter().any(|&(_, start, end, _)| m.start() < end && start < m.end());
+        if m.len() >= min_len && !claimed {
+            if let Some(plain) = base64_decode(m.as_str()).and_then(printable) {
+                decoded.push((PayloadEncoding::Base64, m.start(), m.end(), plain));
+            }
+        }
+    }
+    for m in patterns.url.find_iter(text) {
+        let plain = percent_encoding::percent_decode_str(&m.as_str().replace('+', " ")).collect::<Vec<u8>>();
+        if let Some(plain) = printable(plain) {
+            decoded.push((PayloadEncoding::Url, m.start(), m.end(), plain));
+        }
+    }
+
+    decoded
+}
+
+fn base64_decode(blob: &str) -> Option<Vec<u8>> {
+    let config = GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+    let alphabet = if blob.contains(['-', '_']) { &alphabet::URL_SAFE } else { &alphabet::STANDARD };
+    GeneralPurpose::new(alphabet, config).decode(blob).ok()
+}
+
+/// Decoded bytes that read as text: valid UTF-8 with no control characters
+/// other than whitespace.
+fn printable(bytes: Vec<u8>) -> Option<String> {
+    let text = String::from_utf8(bytes).ok()?;
+    let plausible = !text.trim().is_empty() && text.chars().all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'));
+    plausible.then_some(text)
+}
@@ -0,0 +1,387 @@
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::entropy::EntropyDetector;
+use crate::scanner::ContentOrigin;
+use crate::validators::Validator;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    Pii,
+    Financial,
+    Credential,
+    Confidential,
+    Anomaly,
+    Custom,
+}
+
+/// How strongly the evidence suggests a match is what the detector says.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Confidence {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl Confidence {
+    pub fn raised(self) -> Self {
+        match self {
+            Self::Low => Self::Medium,
+            _ => Self::High,
+        }
+    }
+
+    pub fn lowered(self) -> Self {
+        match self {
+            Self::High => Self::Medium,
+            _ => Self::Low,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub detector_id: String,
+    pub matched: String,
+    pub start: usize,
+    pub end: usize,
+    pub confidence: Confidence,
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl Finding {
+    pub fn new(detector_id: &str, matched: &str, start: usize, end: usize) -> Self {
+        Self {
+            detector_id: detector_id.to_string(),
+            matched: matched.to_string(),
+            start,
+            end,
+            confidence: Confidence::default(),
+            metadata: BTreeMap::new(),
+        }
+    }
+}
+
+/// Default distance, in characters either side of a match, within which a
+/// supporting keyword counts.
+pub const DEFAULT_KEYWORD_WINDOW: usize = 50;
+
+/// Words such as "SSN" or "IBAN" whose presence near a match backs it up.
+#[derive(Debug, Clone)]
+pub struct KeywordRule {
+    keywords: Vec<String>,
+    window: usize,
+    required: bool,
+}
+
+impl KeywordRule {
+    pub fn new(keywords: &[&str], window: usize) -> Self {
+        Self {
+            keywords: keywords.iter().map(|k| k.to_lowercase()).collect(),
+            window,
+            required: false,
+        }
+    }
+
+    /// Drop matches with no keyword nearby instead of lowering their
+    /// confidence, for formats too generic to report on their own.
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Whether any keyword appears, case-insensitively, within `window`
+    /// characters before `start` or after `end`.
+    pub fn near(&self, content: &str, start: usize, end: usize) -> bool {
+        if self.window == 0 {
+            return false;
+        }
+        let from = content[..start]
+            .char_indices()
+            .rev()
+            .nth(self.window - 1)
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        let to = content[end..]
+            .char_indices()
+            .nth(self.window)
+            .map(|(i, _)| end + i)
+            .unwrap_or(content.len());
+
+        let before = content[from..start].to_lowercase();
+        let after = content[end..to].to_lowercase();
+        self.keywords.iter().any(|k| before.contains(k) || after.contains(k))
+    }
+}
+
+/// A named source of findings. Implement this to plug custom detection
+/// logic into `FileMonitor` alongside the built-in detectors.
+pub trait Detector: Send + Sync {
+    /// Stable identifier reported to the backend as the alert's `pattern_type`.
+    fn id(&self) -> &str;
+    fn name(&self) -> &str;
+    fn category(&self) -> Category;
+    fn severity(&self) -> Severity;
+    fn detect(&self, content: &str) -> Vec<Finding>;
+
+    /// Longest match this detector can produce, in bytes, or `None` if it is
+    /// unbounded. Sizes the overlap between chunks of streamed files.
+    fn max_match_len(&self) -> Option<usize> {
+        None
+    }
+
+    /// Whether to run on content extracted from `path` at `origin`.
+    fn applies_to(&self, _path: &Path, _origin: &ContentOrigin) -> bool {
+        true
+    }
+
+    /// Whether this detector only catches what no specific detector
+    /// recognises. Its findings are dropped where they overlap another
+    /// detector's.
+    fn is_fallback(&self) -> bool {
+        false
+    }
+}
+
+/// Reports the whole match, or only the `value` capture group when the
+/// pattern has one, e.g. the password inside a connection string.
+pub struct RegexDetector {
+    id: String,
+    name: String,
+    category: Category,
+    severity: Severity,
+    regex: Regex,
+    max_match_len: Option<usize>,
+    validator: Option<Validator>,
+    confidence: Confidence,
+    keywords: Option<KeywordRule>,
+    file_types: Vec<String>,
+}
+
+impl RegexDetector {
+    pub fn new(
+        id: &str,
+        name: &str,
+        category: Category,
+        severity: Severity,
+        pattern: &str,
+    ) -> Result<Self, regex::Error> {
+        Ok(Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            category,
+            severity,
+            regex: Regex::new(pattern)?,
+            max_match_len: regex_syntax::parse(pattern).ok().and_then(|hir| hir.properties().maximum_len()),
+            validator: None,
+            confidence: Confidence::default(),
+            keywords: None,
+            file_types: Vec::new(),
+        })
+    }
+
+    /// Only report matches that also pass `validator`.
+    pub fn with_validator(mut self, validator: Validator) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Confidence of a match before keyword evidence is considered.
+    pub fn with_confidence(mut self, confidence: Confidence) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Raise confidence by one level when a keyword is nearby and lower it
+    /// by one when none is.
+    pub fn with_keywords(mut self, keywords: KeywordRule) -> Self {
+        self.keywords = Some(keywords);
+        self
+    }
+
+    /// Only scan files with one of these extensions. Inside archives the
+    /// archive itself and every entry on the path count, so `docx` covers
+    /// the parts of a `.docx` and `zip` everything in a `.zip`.
+    pub fn with_file_types(mut self, extensions: &[&str]) -> Self {
+        self.file_types = extensions
+            .iter()
+            .map(|ext| ext.trim_start_matches('.').to_ascii_lowercase())
+            .collect();
+        self
+    }
+}
+
+impl Detector for RegexDetector {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn category(&self) -> Category {
+        self.category
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn max_match_len(&self) -> Option<usize> {
+        self.max_match_len
+    }
+
+    fn applies_to(&self, path: &Path, origin: &ContentOrigin) -> bool {
+        if self.file_types.is_empty() {
+            return true;
+        }
+        let entries = origin.entry.iter().flat_map(|entry| entry.split('/')).map(Path::new);
+        std::iter::once(path).chain(entries).any(|name| {
+            name.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| self.file_types.contains(&ext.to_ascii_lowercase()))
+        })
+    }
+
+    fn detect(&self, content: &str) -> Vec<Finding> {
+        self.regex
+            .captures_iter(content)
+            .filter_map(|caps| {
+                let m = caps.name("value").or_else(|| caps.get(0))?;
+                let mut finding = Finding::new(&self.id, m.as_str(), m.start(), m.end());
+                finding.confidence = match &self.keywords {
+                    Some(keywords) if keywords.near(content, m.start(), m.end()) => self.confidence.raised(),
+                    Some(keywords) if keywords.required => return None,
+                    Some(_) => self.confidence.lowered(),
+                    None => self.confidence,
+                };
+                if let Some(validator) = &self.validator {
+                    if !validator.validate(m.as_str()) {
+                        return None;
+                    }
+                    if let Some((key, value)) = validator.describe(m.as_str()) {
+                        finding.metadata.insert(key.to_string(), value);
+                    }
+                }
+                Some(finding)
+            })
+            .collect()
+    }
+}
+
+pub(crate) type DetectorSpec = (&'static str, &'static str, Category, Severity, Confidence, &'static str, Option<Validator>);
+pub(crate) type KeywordSpec = (&'static str, &'static [&'static str]);
+
+/// Words that make a nearby match much more likely to be what it looks like.
+const SUPPORTING_KEYWORDS: [KeywordSpec; 3] = [
+    ("US_SSN", &["ssn", "social security", "social sec", "taxpayer"]),
+    ("CREDIT_CARD_NUMBER", &["card", "credit", "visa", "mastercard", "amex", "payment", "cvv", "expir"]),
+    ("IBAN", &["iban", "bank", "account", "transfer", "swift", "bic"]),
+];
+
+/// Findings in `text` of every detector that applies to `path` at
+/// `origin`, with the detector's index. Fallback findings overlapping
+/// another detector's are dropped, so a known token is reported once.
+pub(crate) fn detect_all(detectors: &[&dyn Detector], path: &Path, origin: &ContentOrigin, text: &str) -> Vec<(usize, Finding)> {
+    let findings: Vec<(usize, Finding)> = detectors
+        .iter()
+        .enumerate()
+        .filter(|(_, detector)| detector.applies_to(path, origin))
+        .flat_map(|(index, detector)| detector.detect(text).into_iter().map(move |finding| (index, finding)))
+        .collect();
+    let specific: Vec<(usize, usize)> = findings
+        .iter()
+        .filter(|(index, _)| !detectors[*index].is_fallback())
+        .map(|(_, finding)| (finding.start, finding.end))
+        .collect();
+
+    findings
+        .into_iter()
+        .filter(|(index, finding)| {
+            !detectors[*index].is_fallback() || !specific.iter().any(|&(start, end)| finding.start < end && start < finding.end)
+        })
+        .collect()
+}
+
+pub(crate) fn from_specs(specs: &[DetectorSpec], keywords: &[KeywordSpec]) -> Vec<Box<dyn Detector>> {
+    specs
+        .iter()
+        .map(|(id, name, category, severity, confidence, pattern, validator)| {
+            let mut detector = RegexDetector::new(id, name, *category, *severity, pattern)
+                .unwrap()
+                .with_confidence(*confidence);
+            if let Some(validator) = validator {
+                detector = detector.with_validator(*validator);
+            }
+            if let Some((_, keywords)) = keywords.iter().find(|(keyword_id, _)| keyword_id == id) {
+                detector = detector.with_keywords(KeywordRule::new(keywords, DEFAULT_KEYWORD_WINDOW));
+            }
+            Box::new(detector) as Box<dyn Detector>
+        })
+        .collect()
+}
+
+/// The PII and financial detectors, `credential_detectors` and a
+/// lower-confidence `EntropyDetector` for secrets of unknown format.
+pub fn builtin_detectors() -> Vec<Box<dyn Detector>> {
+    let specs: [DetectorSpec; 6] = [
+        ("EMAIL_ADDRESS", "Email address", Category::Pii, Severity::Low, Confidence::Medium,
+            r"(?i)\b[A-Z0-9._%+-]+@[A-Z0-9.-]+\.[A-Z]{2,}\b", None),
+        ("US_SSN", "US Social Security number", Category::Pii, Severity::High, Confidence::Medium,
+            r"\b\d{3}-\d{2}-\d{4}\b", Some(Validator::UsSsn)),
+        ("CREDIT_CARD_NUMBER", "Credit card number", Category::Financial, Severity::High, Confidence::Medium,
+            r"\b\d(?:[ -]?\d){11,18}\b", Some(Validator::PaymentCard)),
+        ("IBAN", "International bank account number", Category::Financial, Severity::High, Confidence::Medium,
+            r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b", Some(Validator::Iban)),
+        ("PASSWORD_ASSIGNMENT", "Password assignment", Category::Credential, Severity::High, Confidence::Medium,
+            r"(?i)password.*=.*", None),
+        ("API_KEY_ASSIGNMENT", "API or secret key assignment", Category::Credential, Severity::High, Confidence::Medium,
+            r"(?i)(api[_-]?key|secret[_-]?key).*=.*", None),
+    ];
+
+    let mut detectors = from_specs(&specs, &SUPPORTING_KEYWORDS);
+    detectors.extend(credential_detectors());
+    detectors.push(Box::new(EntropyDetector::default()));
+    detectors
+}
+
+/// Cloud provider, SaaS and developer credentials with well-known formats.
+pub fn credential_detectors() -> Vec<Box<dyn Detector>> {
+    let specs: [DetectorSpec; 11] = [
+        ("AWS_ACCESS_KEY_ID", "AWS access key ID", Category::Credential, Severity::High, Confidence::High,
+            r"\b(?:AKIA|ASIA|ABIA|ACCA)[0-9A-Z]{16}\b", None),
+        ("AWS_SECRET_ACCESS_KEY", "AWS secret access key", Category::Credential, Severity::Critical, Confidence::High,
+            r#"(?i)\baws_?(?:secret_?access_?key|secret_?key)\b["']?\s*[:=]\s*["']?(?P<value>[A-Za-z0-9/+]{40})(?:[^A-Za-z0-9/+=]|$)"#, None),
+        ("GITHUB_TOKEN", "GitHub token", Category::Credential, Severity::High, Confidence::High,
+            r"\b(?:gh[pousr]_[A-Za-z0-9]{36}|github_pat_[A-Za-z0-9_]{82})\b", None),
+        ("GITLAB_TOKEN", "GitLab personal access token", Category::Credential, Severity::High, Confidence::High,
+            r"\bglpat-[A-Za-z0-9_-]{20}", None),
+        ("SLACK_TOKEN", "Slack token", Category::Credential, Severity::High, Confidence::High,
+            r"\bxox[abposr]-[A-Za-z0-9-]{10,250}", None),
+        ("GOOGLE_API_KEY", "Google API key", Category::Credential, Severity::Medium, Confidence::High,
+            r"\bAIza[0-9A-Za-z_-]{35}", None),
+        ("AZURE_CONNECTION_STRING", "Azure storage or service bus connection string", Category::Credential, Severity::Critical, Confidence::High,
+            r"(?i)\b(?:AccountKey|SharedAccessKey)=(?P<value>[A-Za-z0-9+/]{43,86}={0,2})", None),
+        ("STRIPE_API_KEY", "Stripe API key", Category::Credential, Severity::High, Confidence::High,
+            r"\b(?:sk|rk)_(?:live|test)_[0-9A-Za-z]{24,99}\b", None),
+        ("JSON_WEB_TOKEN", "JSON Web Token", Category::Credential, Severity::Medium, Confidence::Medium,
+            r"\beyJ[A-Za-z0-9_-]{8,}\.eyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}", None),
+        ("PRIVATE_KEY", "PEM or OpenSSH private key", Category::Credential, Severity::Critical, Confidence::High,
+            r"-----BEGIN (?:RSA |DSA |EC |OPENSSH |ENCRYPTED |PGP )?PRIVATE KEY(?: BLOCK)?-----", None),
+        ("DATABASE_URI_PASSWORD", "Database connection URI with password", Category::Credential, Severity::High, Confidence::High,
+            r"(?i)\b(?:postgres(?:ql)?|mysql|mariadb|mongodb(?:\+srv)?|rediss?|amqps?|mssql|sqlserver|oracle)://[^\s:/@]+:(?P<value>[^\s@/]+)@[^\s/]+", None),
+    ];
+
+    from_specs(&specs, &[])
+}
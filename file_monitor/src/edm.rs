@@ -0,0 +1,177 @@
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+use std::io::Read;
+use std::path::Path;
+
+/// Values shorter than this (after normalisation) are too common to be
+/// evidence of anything and are left out of the index.
+const MIN_VALUE_LEN: usize = 3;
+
+/// Values held by more records than this (a shared city, "N/A") say nothing
+/// about which record a file came from, so scanning ignores them.
+const MAX_RECORDS_PER_VALUE: usize = 10;
+
+/// Exact Data Match index: salted SHA-256 hashes of every value in a table
+/// of real sensitive records, so endpoints can recognise them without ever
+/// holding the plaintext.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EdmIndex {
+    salt: String,
+    /// Column names from the source CSV header.
+    pub fields: Vec<String>,
+    /// Distinct fields of one record that must appear in a file.
+    pub min_fields: usize,
+    /// Most tokens in any indexed value, so scanning knows how many
+    /// consecutive tokens to try.
+    max_tokens: usize,
+    /// Value hash to the (record, field) pairs holding that value.
+    entries: HashMap<String, Vec<(u32, u16)>>,
+    /// Hex SHA-256 of the index contents, set when it is built or loaded.
+    #[serde(skip)]
+    digest: String,
+}
+
+/// One indexed value found in scanned text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdmHit {
+    pub record: u32,
+    pub field: u16,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A record with at least `min_fields` of its fields present in one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdmRecordMatch {
+    pub record: u32,
+    pub fields: BTreeSet<u16>,
+}
+
+impl EdmIndex {
+    /// Builds an index from CSV with a header row. Every non-empty cell is
+    /// normalised, salted and hashed; nothing else from the CSV is kept.
+    pub fn build<R: Read>(csv: R, salt: &str, min_fields: usize) -> Result<Self, Box<dyn Error>> {
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(csv);
+        let fields: Vec<String> = reader.headers()?.iter().map(|h| h.trim().to_string()).collect();
+        let mut index = Self {
+            salt: salt.to_string(),
+            fields,
+            min_fields: min_fields.max(1),
+            max_tokens: 1,
+            entries: HashMap::new(),
+            digest: String::new(),
+        };
+
+        for (record, row) in reader.records().enumerate() {
+            for (field, value) in row?.iter().enumerate() {
+                let tokens: Vec<&str> = tokenize(value).map(|(token, _, _)| token).collect();
+                let normalized = normalize(&tokens);
+                if normalized.chars().count() < MIN_VALUE_LEN {
+                    continue;
+                }
+                index.max_tokens = index.max_tokens.max(tokens.len());
+                let hits = index.entries.entry(index.hash(&normalized)).or_default();
+                hits.push((record as u32, field as u16));
+            }
+        }
+
+        index.digest = index.compute_digest();
+        Ok(index)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut index: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        index.digest = index.compute_digest();
+        Ok(index)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn record_count(&self) -> usize {
+        self.entries.values().flatten().map(|(record, _)| record).collect::<BTreeSet<_>>().len()
+    }
+
+    /// Identifies the index contents, for the scan cache's detector version.
+    pub fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    fn compute_digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{}\n{:?}\n{}\n{}\n", self.salt, self.fields, self.min_fields, self.max_tokens));
+        for (hash, entries) in self.entries.iter().collect::<BTreeMap<_, _>>() {
+            hasher.update(format!("{}{:?}\n", hash, entries));
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    fn hash(&self, normalized: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.salt.as_bytes());
+        hasher.update(normalized.as_bytes());
+        hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Every run of up to `max_tokens` consecutive tokens in `text` whose
+    /// hash is in the index and held by at most `MAX_RECORDS_PER_VALUE`
+    /// records.
+    pub fn find(&self, text: &str) -> Vec<EdmHit> {
+        let tokens: Vec<(&str, usize, usize)> = tokenize(text).collect();
+        let mut hits = Vec::new();
+
+        for i in 0..tokens.len() {
+            for n in 1..=self.max_tokens.min(tokens.len() - i) {
+                let run = &tokens[i..i + n];
+                let words: Vec<&str> = run.iter().map(|(token, _, _)| *token).collect();
+                let normalized = normalize(&words);
+                if normalized.chars().count() < MIN_VALUE_LEN {
+                    continue;
+                }
+                let Some(entries) = self.entries.get(&self.hash(&normalized)) else {
+                    continue;
+                };
+                if entries.len() <= MAX_RECORDS_PER_VALUE {
+                    let (start, end) = (run[0].1, run[n - 1].2);
+                    hits.extend(entries.iter().map(|&(record, field)| EdmHit { record, field, start, end }));
+                }
+            }
+        }
+
+        hits
+    }
+
+    /// Records with at least `min_fields` distinct fields among `hits`.
+    pub fn co_occurring(&self, hits: &[EdmHit]) -> Vec<EdmRecordMatch> {
+        let mut fields: BTreeMap<u32, BTreeSet<u16>> = BTreeMap::new();
+        for hit in hits {
+            fields.entry(hit.record).or_default().insert(hit.field);
+        }
+
+        fields
+            .into_iter()
+            .filter(|(_, fields)| fields.len() >= self.min_fields)
+            .map(|(record, fields)| EdmRecordMatch { record, fields })
+            .collect()
+    }
+}
+
+/// Splits on whitespace and punctuation that never appears inside an
+/// identifier, keeping `@ . - _ +` so emails and phone numbers stay whole.
+fn tokenize(text: &str) -> impl Iterator<Item = (&str, usize, usize)> {
+    text.split(|c: char| c.is_whitespace() || ",;:()[]{}<>\"'|/\\=".contains(c))
+        .map(move |part| {
+            let trimmed = part.trim_matches(|c: char| ".-_+".contains(c));
+            let start = trimmed.as_ptr() as usize - text.as_ptr() as usize;
+            (trimmed, start, start + trimmed.len())
+        })
+        .filter(|(token, _, _)| !token.is_empty())
+}
+
+fn normalize(tokens: &[&str]) -> String {
+    tokens.join(" ").to_lowercase()
+}
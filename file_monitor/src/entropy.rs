@@ -0,0 +1,139 @@
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+use crate::detectors::{Category, Confidence, Detector, Finding, KeywordRule, Severity, DEFAULT_KEYWORD_WINDOW};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct EntropyThresholds {
+    /// Shortest token considered, in characters.
+    pub min_length: usize,
+    /// Longer tokens are usually embedded data (images, fonts), not secrets.
+    pub max_length: usize,
+    /// Bits per character for tokens in the base64 alphabet (at most 6).
+    pub base64_bits: f64,
+    /// Bits per character for hex tokens (at most 4).
+    pub hex_bits: f64,
+}
+
+impl Default for EntropyThresholds {
+    fn default() -> Self {
+        Self {
+            min_length: 20,
+            max_length: 128,
+            base64_bits: 4.5,
+            hex_bits: 3.0,
+        }
+    }
+}
+
+/// Flags random-looking base64 and hex tokens that no format-specific
+/// detector recognises. Findings are low confidence unless a word such as
+/// "secret" or "token" is nearby, and carry the measured entropy in their
+/// metadata.
+pub struct EntropyDetector {
+    thresholds: EntropyThresholds,
+    token: Regex,
+    uuid: Regex,
+    hash_line: Regex,
+    keywords: KeywordRule,
+}
+
+impl EntropyDetector {
+    pub fn new(thresholds: EntropyThresholds) -> Self {
+        Self {
+            thresholds,
+            token: Regex::new(r"[A-Za-z0-9+/=_-]+").unwrap(),
+            uuid: Regex::new(r"^[0-9A-Fa-f]{8}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{12}$").unwrap(),
+            // Integrity hashes in lockfiles, checksum files and SRI attributes
+            hash_line: Regex::new(r"(?i)checksum|integrity|digest|\bhash|resolved|\bsha(?:1|224|256|384|512)\b|\bmd5\b|\bh1:").unwrap(),
+            keywords: KeywordRule::new(&["secret", "token", "key", "passw", "auth", "credential"], DEFAULT_KEYWORD_WINDOW),
+        }
+    }
+}
+
+impl Default for EntropyDetector {
+    fn default() -> Self {
+        Self::new(EntropyThresholds::default())
+    }
+}
+
+impl Detector for EntropyDetector {
+    fn id(&self) -> &str {
+        "GENERIC_SECRET"
+    }
+
+    fn name(&self) -> &str {
+        "High-entropy string"
+    }
+
+    fn category(&self) -> Category {
+        Category::Credential
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Medium
+    }
+
+    fn max_match_len(&self) -> Option<usize> {
+        Some(self.thresholds.max_length)
+    }
+
+    fn is_fallback(&self) -> bool {
+        true
+    }
+
+    fn detect(&self, content: &str) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        for m in self.token.find_iter(content) {
+            let token = m.as_str().trim_end_matches('=');
+            if token.len() < self.thresholds.min_length || token.len() > self.thresholds.max_length {
+                continue;
+            }
+            if self.uuid.is_match(token) {
+                continue;
+            }
+
+            let hex = token.chars().all(|c| c.is_ascii_hexdigit());
+            let threshold = if hex { self.thresholds.hex_bits } else { self.thresholds.base64_bits };
+            // Random base64 of this length virtually always has a digit; words and identifiers rarely do
+            if !hex && !token.chars().any(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            let entropy = shannon_entropy(token);
+            if entropy < threshold {
+                continue;
+            }
+
+            let line_start = content[..m.start()].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let line_end = content[m.end()..].find('\n').map(|i| m.end() + i).unwrap_or(content.len());
+            if self.hash_line.is_match(&content[line_start..line_end]) {
+                continue;
+            }
+
+            let mut finding = Finding::new(self.id(), token, m.start(), m.start() + token.len());
+            finding.metadata.insert("entropy".to_string(), format!("{:.2}", entropy));
+            finding.confidence = if self.keywords.near(content, m.start(), m.end()) { Confidence::Medium } else { Confidence::Low };
+            findings.push(finding);
+        }
+
+        findings
+    }
+}
+
+/// Shannon entropy of `text` in bits per character.
+pub fn shannon_entropy(text: &str) -> f64 {
+    let mut counts = std::collections::HashMap::new();
+    for c in text.chars() {
+        *counts.entry(c).or_insert(0usize) += 1;
+    }
+    let len = text.chars().count() as f64;
+
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
@@ -0,0 +1,248 @@
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::path::Path;
+use std::sync::OnceLock;
+use crate::scanner::{ContentScanner, ScannedContent};
+
+/// Multiplier for the polynomial rolling hash over word hashes.
+const ROLLING_BASE: u64 = 0x100000001b3;
+
+/// Shingle fingerprints of registered confidential documents. A scanned file
+/// matches a document when it contains at least `min_similarity` of that
+/// document's shingles, so partial copies and light edits are still caught.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FingerprintIndex {
+    /// Words per shingle. Longer shingles give fewer chance matches but are
+    /// broken by smaller edits.
+    pub shingle_words: usize,
+    /// Share of a document's shingles (0.0 to 1.0) a file must contain.
+    pub min_similarity: f64,
+    documents: Vec<SourceDocument>,
+    /// Shingle hash to the documents containing it.
+    shingles: HashMap<u64, Vec<u32>>,
+    /// Hex SHA-256 of the index contents, computed on first use after the
+    /// last registration.
+    #[serde(skip)]
+    digest: OnceLock<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SourceDocument {
+    pub name: String,
+    pub shingle_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentMatch {
+    pub document: String,
+    /// Share of the document's shingles found in the file.
+    pub similarity: f64,
+    pub shared_shingles: usize,
+}
+
+impl Default for FingerprintIndex {
+    fn default() -> Self {
+        Self::new(8, 0.3)
+    }
+}
+
+impl FingerprintIndex {
+    pub fn new(shingle_words: usize, min_similarity: f64) -> Self {
+        Self {
+            shingle_words: shingle_words.max(1),
+            min_similarity,
+            documents: Vec::new(),
+            shingles: HashMap::new(),
+            digest: OnceLock::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn documents(&self) -> &[SourceDocument] {
+        &self.documents
+    }
+
+    pub fn shingler(&self) -> Shingler {
+        Shingler::new(self.shingle_words)
+    }
+
+    /// Registers `text` as a confidential document called `name`.
+    pub fn register_text(&mut self, name: &str, text: &str) {
+        let mut shingler = self.shingler();
+        shingler.feed(text, true);
+        self.register(name, shingler.take().into_iter().collect());
+    }
+
+    /// Registers a file using the same text extraction as scanning, so any
+    /// format the scanner reads (PDF, Office, archives) can be fingerprinted.
+    pub async fn register_file(&mut self, scanner: &ContentScanner, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut shingler = self.shingler();
+        let mut shingles = HashSet::new();
+        // A little overlap marks streamed chunks that end mid-word as continuing
+        scanner.scan_each(path, 1, |content| {
+            shingler.feed_content(&content);
+            shingles.extend(shingler.take());
+        }).await?;
+        shingler.finish();
+        shingles.extend(shingler.take());
+
+        self.register(&path.to_string_lossy(), shingles);
+        Ok(())
+    }
+
+    fn register(&mut self, name: &str, shingles: HashSet<u64>) {
+        self.digest.take();
+        let id = self.documents.len() as u32;
+        self.documents.push(SourceDocument {
+            name: name.to_string(),
+            shingle_count: shingles.len(),
+        });
+        for shingle in shingles {
+            self.shingles.entry(shingle).or_default().push(id);
+        }
+    }
+
+    /// Identifies the index contents, for the scan cache's detector version.
+    pub fn digest(&self) -> &str {
+        self.digest.get_or_init(|| {
+            let mut hasher = Sha256::new();
+            hasher.update(format!("{}\n{}\n{:?}\n", self.shingle_words, self.min_similarity, self.documents));
+            for (shingle, documents) in self.shingles.iter().collect::<BTreeMap<_, _>>() {
+                hasher.update(format!("{}{:?}\n", shingle, documents));
+            }
+            hex::encode(hasher.finalize())
+        })
+    }
+
+    /// Whether `shingle` occurs in any registered document; scans only need
+    /// to keep these.
+    pub fn contains(&self, shingle: u64) -> bool {
+        self.shingles.contains_key(&shingle)
+    }
+
+    /// Documents sharing at least `min_similarity` of their shingles with
+    /// `shingles`, most similar first.
+    pub fn matches(&self, shingles: &HashSet<u64>) -> Vec<DocumentMatch> {
+        let mut shared = vec![0usize; self.documents.len()];
+        for shingle in shingles {
+            for &id in self.shingles.get(shingle).into_iter().flatten() {
+                shared[id as usize] += 1;
+            }
+        }
+
+        let mut matches: Vec<DocumentMatch> = self
+            .documents
+            .iter()
+            .zip(shared)
+            .filter(|(document, _)| document.shingle_count > 0)
+            .map(|(document, shared)| DocumentMatch {
+                document: document.name.clone(),
+                similarity: shared as f64 / document.shingle_count as f64,
+                shared_shingles: shared,
+            })
+            .filter(|m| m.similarity >= self.min_similarity)
+            .collect();
+        matches.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+        matches
+    }
+}
+
+/// Turns a stream of text into hashes of every run of `words` consecutive
+/// words, using a rolling hash so each word is only hashed once. Words are
+/// lowercased runs of letters and digits, so punctuation, layout and case
+/// changes do not affect the result.
+pub struct Shingler {
+    words: usize,
+    window: VecDeque<u64>,
+    hash: u64,
+    /// `ROLLING_BASE` to the power `words - 1`, to remove the oldest word.
+    leading: u64,
+    partial: String,
+    pending: Vec<u64>,
+}
+
+impl Shingler {
+    pub fn new(words: usize) -> Self {
+        let words = words.max(1);
+        Self {
+            words,
+            window: VecDeque::with_capacity(words),
+            hash: 0,
+            leading: (1..words).fold(1u64, |power, _| power.wrapping_mul(ROLLING_BASE)),
+            partial: String::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Feeds the next piece of text. When `complete` is false the text may
+    /// end part way through a word, which is held back until the next call.
+    pub fn feed(&mut self, text: &str, complete: bool) {
+        let mut word = std::mem::take(&mut self.partial);
+        for c in text.chars() {
+            if c.is_alphanumeric() {
+                word.extend(c.to_lowercase());
+            } else if !word.is_empty() {
+                self.push_word(&word);
+                word.clear();
+            }
+        }
+
+        if complete {
+            if !word.is_empty() {
+                self.push_word(&word);
+            }
+        } else {
+            self.partial = word;
+        }
+    }
+
+    /// Feeds scanned content, skipping the overlap a streamed chunk repeats
+    /// from the next one so every word is seen exactly once.
+    pub fn feed_content(&mut self, content: &ScannedContent) {
+        let complete = !content.text[content.owned_len..].starts_with(char::is_alphanumeric);
+        self.feed(&content.text[..content.owned_len], complete);
+    }
+
+    /// Flushes a word held back by the last `feed`.
+    pub fn finish(&mut self) {
+        let word = std::mem::take(&mut self.partial);
+        if !word.is_empty() {
+            self.push_word(&word);
+        }
+    }
+
+    /// Shingle hashes produced since the last call.
+    pub fn take(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.pending)
+    }
+
+    fn push_word(&mut self, word: &str) {
+        let word = fnv1a(word);
+        if self.window.len() == self.words {
+            let oldest = self.window.pop_front().unwrap_or_default();
+            self.hash = self.hash.wrapping_sub(oldest.wrapping_mul(self.leading));
+        }
+        self.window.push_back(word);
+        self.hash = self.hash.wrapping_mul(ROLLING_BASE).wrapping_add(word);
+
+        if self.window.len() == self.words {
+            self.pending.push(self.hash);
+        }
+    }
+}
+
+/// FNV-1a, chosen over `DefaultHasher` because saved indexes need hashes
+/// that stay the same across builds.
+fn fnv1a(word: &str) -> u64 {
+    word.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
@@ -1,25 +1,79 @@
 ﻿use std::path::{Path, PathBuf};
-use std::io::Read;
 use std::error::Error;
-use zip::ZipArchive;
-use tokio::sync::mpsc;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinSet;
 use notify::Event;
 use notify::RecursiveMode;
 use notify::Watcher;
-use calamine::{open_workbook, Reader, Xlsx};
 use serde::{Serialize, Deserialize};
+use cache::{CacheCheck, FileStamp};
+use decoding::decode_payloads;
+use detectors::detect_all;
+use edm::EdmHit;
+use std::sync::{Arc, Mutex, OnceLock};
+use yara::{YaraHits, YaraTask};
+
+pub mod baseline;
+pub mod cache;
+pub mod config;
+pub mod debounce;
+pub mod decoding;
+pub mod detectors;
+pub mod edm;
+pub mod entropy;
+pub mod fingerprint;
+pub mod location;
+pub mod quarantine;
+pub mod redaction;
+pub mod regional;
+pub mod rules;
+pub mod scanner;
+pub mod scope;
+pub mod validators;
+pub mod workers;
+pub mod yara;
+
+pub use baseline::{BaselineConfig, BaselineCrawl, BaselineState};
+pub use cache::{CacheConfig, ScanCache};
+pub use config::MonitorConfig;
+pub use debounce::{DebounceConfig, Debouncer};
+pub use decoding::{DecodedPayload, PayloadEncoding};
+pub use detectors::{Category, Confidence, Detector, Finding, KeywordRule, RegexDetector, Severity};
+pub use edm::EdmIndex;
+pub use entropy::{EntropyDetector, EntropyThresholds};
+pub use fingerprint::{DocumentMatch, FingerprintIndex};
+pub use location::MatchLocation;
+pub use quarantine::{Quarantine, QuarantineConfig, QuarantineManifest};
+pub use redaction::{RedactionConfig, RedactionMode};
+pub use regional::DetectorPack;
+pub use rules::RuleSet;
+pub use scanner::{ContentOrigin, ContentScanner, LimitViolation, ScanOutput, ScannedContent, TextPosition};
+pub use scope::{FileScope, ScopeConfig, WatchRoot};
+pub use validators::Validator;
+pub use workers::{ScanQueue, WorkerConfig};
+pub use yara::{YaraMatch, YaraRules};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Alert {
     pub device_id: String,
     pub file_path: String,
     pub pattern_type: String,
+    pub detector_name: String,
+    pub category: Category,
+    pub severity: Severity,
+    /// Highest confidence among the reported matches.
+    pub confidence: Confidence,
     pub matched_content: String,
+    pub redaction: RedactionMode,
+    pub match_count: usize,
+    pub locations: Vec<MatchLocation>,
+    pub metadata: BTreeMap<String, String>,
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
 pub struct Communication {
-    alerts: std::sync::Arc<tokio::sync::Mutex<Vec<Alert>>>,
+    pub alerts: std::sync::Arc<tokio::sync::Mutex<Vec<Alert>>>,
     device_id: String,
     api_endpoint: String,
     client: reqwest::Client,
@@ -37,14 +91,15 @@ impl Communication {
 
     pub async fn send_alert(&self, alert: Alert) -> Result<(), Box<dyn Error>> {
         let mut alerts = self.alerts.lock().await;
-        println!("⚠️ Alert: Found {} in file {}: {}", 
+        println!("⚠️ Alert: Found {} in file {} ({} matches): {}", 
             alert.pattern_type, 
             alert.file_path, 
+            alert.match_count,
             alert.matched_content
         );
 
         let response = self.client
-            .post(&format!("{}/alerts", self.api_endpoint))
+            .post(format!("{}/alerts", self.api_endpoint))
             .json(&alert)
             .send()
             .await?;
@@ -77,105 +132,165 @@ impl Communication {
     }
 }
 
-struct ContentScanner {
-    infer: infer::Infer,
+/// A file from the baseline crawl, and a sender dropped once it is scanned.
+type CrawledFile = (PathBuf, oneshot::Sender<()>);
+
+pub struct FileMonitor {
+    comm: Communication,
+    detectors: Vec<Box<dyn Detector>>,
+    content_scanner: ContentScanner,
+    config: MonitorConfig,
+    edm: Option<EdmIndex>,
+    fingerprints: Option<FingerprintIndex>,
+    rules: Option<RuleSet>,
+    yara: Option<YaraRules>,
+    cache: Option<Mutex<ScanCache>>,
+    /// Held while the cache is written, so an older copy never lands last.
+    flushing: tokio::sync::Mutex<()>,
+    /// Set from the watch roots when monitoring starts.
+    scope: Arc<FileScope>,
+    /// Everything in `detector_version` except the rule file, which can
+    /// change while monitoring.
+    base_version: OnceLock<String>,
 }
 
-impl ContentScanner {
-    fn new() -> Self {
-        Self { infer: infer::Infer::new() }
+impl FileMonitor {
+    pub fn new(comm: Communication) -> Self {
+        Self::with_detectors(comm, detectors::builtin_detectors())
     }
 
-    async fn scan(&self, path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
-        let kind = self.infer.get_from_path(path)?;
-        
-        match kind.map(|k| k.mime_type()) {
-            Some("application/pdf") => self.scan_pdf(path).await,
-            Some("application/xlsx") => self.scan_excel(path).await,
-            Some("application/zip") => self.scan_zip(path).await,
-            _ => self.scan_text(path).await,
+    pub fn with_detectors(comm: Communication, detectors: Vec<Box<dyn Detector>>) -> Self {
+        Self {
+            comm,
+            detectors,
+            content_scanner: ContentScanner::new(),
+            config: MonitorConfig::default(),
+            edm: None,
+            fingerprints: None,
+            rules: None,
+            yara: None,
+            cache: None,
+            flushing: tokio::sync::Mutex::new(()),
+            scope: Arc::new(FileScope::default()),
+            base_version: OnceLock::new(),
         }
     }
 
-    async fn scan_zip(&self, path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
-        let file = std::fs::File::open(path)?;
-        let mut archive = ZipArchive::new(file)?;
-        let mut text = Vec::new();
-
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)?;
-            let mut content = String::new();
-            file.read_to_string(&mut content)?;
-            text.push(content);
+    pub fn with_config(mut self, config: MonitorConfig) -> Self {
+        if let Err(e) = config.redaction.validate() {
+            eprintln!("{}; matched values will be fully masked", e);
         }
+        self.content_scanner = ContentScanner::with_config(config.scanner.clone());
+        self.cache = config.cache.enabled.then(|| Mutex::new(ScanCache::load(&config.cache.path)));
+        self.config = config;
+        self.base_version.take();
+        self
+    }
 
-        Ok(text)
+    /// Alert when several fields of one record in `index` appear in a file.
+    pub fn with_edm_index(mut self, index: EdmIndex) -> Self {
+        self.edm = Some(index);
+        self.base_version.take();
+        self
     }
 
-    async fn scan_excel(&self, path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
-        let mut workbook: Xlsx<_> = open_workbook(path)?;
-        let mut text = Vec::new();
-        
-        let sheet_names = workbook.sheet_names().to_owned();
-        for name in sheet_names {
-            if let Some(Ok(range)) = workbook.worksheet_range(&name) {
-                for row in range.rows() {
-                    let row_text: String = row.iter()
-                        .map(|cell| cell.to_string())
-                        .collect::<Vec<String>>()
-                        .join(" ");
-                    text.push(row_text);
-                }
-            }
-        }
+    /// Alert when a file contains a substantial part of a document in `index`.
+    pub fn with_fingerprints(mut self, index: FingerprintIndex) -> Self {
+        self.fingerprints = Some(index);
+        self.base_version.take();
+        self
+    }
 
-        Ok(text)
+    /// Adds the detectors defined in a TOML or YAML rule file. The file is
+    /// validated now and reloaded whenever it changes while monitoring; its
+    /// rules may not reuse the id of a registered detector.
+    pub fn with_rule_file(mut self, path: &Path) -> Result<Self, Box<dyn Error>> {
+        self.rules = Some(RuleSet::load(path, &self.detector_ids())?);
+        Ok(self)
     }
 
-    async fn scan_pdf(&self, path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
-        let doc = lopdf::Document::load(path)?;
-        let mut text = Vec::new();
+    /// Evaluate `rules` against each file's raw bytes and its extracted text.
+    pub fn with_yara_rules(mut self, rules: YaraRules) -> Self {
+        self.yara = Some(rules);
+        self.base_version.take();
+        self
+    }
 
-        for page_num in doc.get_pages().keys() {
-            if let Ok(content) = doc.extract_text(&[*page_num]) {
-                text.push(content);
-            }
+    /// Adds the detector packs `MonitorConfig::policy_groups` lists for
+    /// `group`. Call after `with_config`.
+    pub fn with_policy_group(mut self, group: &str) -> Self {
+        let packs = self.config.policy_groups.get(group).cloned().unwrap_or_default();
+        for pack in packs {
+            self.detectors.extend(pack.detectors());
         }
+        self.base_version.take();
+        self
+    }
 
-        Ok(text)
+    pub fn register_detector(&mut self, detector: Box<dyn Detector>) {
+        self.detectors.push(detector);
+        self.base_version.take();
     }
 
-    async fn scan_text(&self, path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
-        Ok(vec![std::fs::read_to_string(path)?])
+    fn detector_ids(&self) -> Vec<&str> {
+        self.detectors.iter().map(|detector| detector.id()).collect()
     }
-}
 
-pub struct FileMonitor {
-    comm: Communication,
-    patterns: Vec<regex::Regex>,
-    content_scanner: ContentScanner,
-}
+    pub fn detectors(&self) -> &[Box<dyn Detector>] {
+        &self.detectors
+    }
 
-impl FileMonitor {
-    pub fn new(comm: Communication) -> Self {
-        let patterns = vec![
-            regex::Regex::new(r"\b[A-Z0-9._%+-]+@[A-Z0-9.-]+\.[A-Z]{2,}\b").unwrap(),
-            regex::Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").unwrap(),
-            regex::Regex::new(r"\b\d{4}[- ]?\d{4}[- ]?\d{4}[- ]?\d{4}\b").unwrap(),
-            regex::Regex::new(r"(?i)password.*=.*").unwrap(),
-            regex::Regex::new(r"\b(?:\d[ -]*?){13,16}\b").unwrap(),
-            regex::Regex::new(r"(?i)(api[_-]?key|secret[_-]?key).*=.*").unwrap(),
-        ];
+    pub fn rules(&self) -> Option<&RuleSet> {
+        self.rules.as_ref()
+    }
 
-        Self {
-            comm,
-            patterns,
-            content_scanner: ContentScanner::new(),
+    /// Identifies everything that decides what a scan finds: detectors,
+    /// rules, indexes and scan settings. Cached results are only reused
+    /// under the same version.
+    pub fn detector_version(&self) -> String {
+        let base = self.base_version.get_or_init(|| {
+            let mut parts = vec![env!("CARGO_PKG_VERSION").to_string()];
+            for detector in &self.detectors {
+                parts.push(format!(
+                    "{}|{}|{:?}|{:?}|{:?}",
+                    detector.id(),
+                    detector.name(),
+                    detector.category(),
+                    detector.severity(),
+                    detector.max_match_len()
+                ));
+            }
+            parts.push(self.config.detection_settings());
+            parts.push(self.edm.as_ref().map(|edm| edm.digest().to_string()).unwrap_or_default());
+            parts.push(self.fingerprints.as_ref().map(|index| index.digest().to_string()).unwrap_or_default());
+            parts.push(self.yara.as_ref().map(|yara| yara.digest().to_string()).unwrap_or_default());
+            cache::digest(parts.join("\n").as_bytes())
+        });
+        let rules = self.rules.as_ref().map(RuleSet::digest).unwrap_or_default();
+        cache::digest(format!("{}\n{}", base, rules).as_bytes())[..16].to_string()
+    }
+
+    /// Watches `path` until the watcher stops, with the size, type and
+    /// ignored directory limits of `MonitorConfig::scope`.
+    pub async fn start_monitoring(self, path: &Path) -> Result<(), Box<dyn Error>> {
+        self.watch(&[WatchRoot::new(path)]).await
+    }
+
+    /// Watches every root in `MonitorConfig::roots` until the watcher stops.
+    pub async fn start_monitoring_roots(self) -> Result<(), Box<dyn Error>> {
+        let roots = self.config.roots.clone();
+        if roots.is_empty() {
+            return Err("no watch roots configured".into());
         }
+        self.watch(&roots).await
     }
 
-    pub async fn start_monitoring(&self, path: &Path) -> Result<(), Box<dyn Error>> {
-        println!("Starting file monitor for device: {}", self.comm.device_id);
+    /// Debounced events and the baseline crawl feed a `ScanQueue`, which up
+    /// to `WorkerConfig::max_workers` scans drain concurrently.
+    async fn watch(mut self, roots: &[WatchRoot]) -> Result<(), Box<dyn Error>> {
+        self.scope = Arc::new(FileScope::new(roots, &self.config.scope)?);
+        let monitor = Arc::new(self);
+        println!("Starting file monitor for device: {}", monitor.comm.device_id);
         let (tx, mut rx) = mpsc::channel(100);
 
         let mut watcher = notify::recommended_watcher(move |res: Result<Event, _>| {
@@ -184,42 +299,560 @@ impl FileMonitor {
             }
         })?;
 
-        watcher.watch(path, RecursiveMode::Recursive)?;
-        println!("Monitoring directory: {:?}", path);
+        let roots = monitor.scope.roots();
+        for root in &roots {
+            watcher.watch(root, RecursiveMode::Recursive)?;
+            println!("Monitoring directory: {:?}", root);
+        }
 
-        while let Some(event) = rx.recv().await {
-            if let notify::EventKind::Create(_) | notify::EventKind::Modify(_) = event.kind {
-                for path_buf in event.paths {
-                    let path = path_buf.clone();
-                    if let Err(e) = self.scan_file(&path_buf).await {
-                        eprintln!("Error scanning file {:?}: {}", path, e);
+        let (reload_tx, mut reload_rx) = mpsc::channel(1);
+        let _rule_watcher = match &monitor.rules {
+            Some(rules) => {
+                println!("Watching rule file: {:?}", rules.path());
+                Some(rules.watch(reload_tx)?)
+            }
+            None => None,
+        };
+
+        // Crawled files are handed over one at a time, so the crawl waits
+        // whenever the queue is full
+        let (crawl_tx, mut crawl_rx) = mpsc::channel(1);
+        let baseline = Self::run_baseline(monitor.clone(), roots.clone(), crawl_tx);
+        tokio::pin!(baseline);
+        let mut baseline_done = !monitor.config.baseline.enabled;
+
+        // One save emits several events; scan each path once they stop
+        let mut debouncer = Debouncer::new(monitor.config.debounce.clone());
+        let workers = &monitor.config.workers;
+        let mut queue = ScanQueue::new(workers.max_queued);
+        let mut running = JoinSet::new();
+        let mut running_paths: HashMap<tokio::task::Id, PathBuf> = HashMap::new();
+        // Crawled files whose scan the crawl is waiting for
+        let mut waiting: HashMap<PathBuf, Vec<oneshot::Sender<()>>> = HashMap::new();
+
+        loop {
+            let deadline = debouncer.next_deadline().map(tokio::time::Instant::from_std);
+            let room = !queue.is_full();
+            tokio::select! {
+                event = rx.recv() => {
+                    let Some(event) = event else { break };
+                    if let notify::EventKind::Create(_) | notify::EventKind::Modify(_) = event.kind {
+                        for path_buf in event.paths.into_iter().filter(|p| monitor.scope.allows_file(p)) {
+                            debouncer.event(path_buf, std::time::Instant::now());
+                        }
+                    }
+                }
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(tokio::time::Instant::now)), if deadline.is_some() && room => {
+                    for path_buf in debouncer.ready_at_most(std::time::Instant::now(), queue.space()) {
+                        queue.push(path_buf);
+                    }
+                }
+                Some((file, done)) = crawl_rx.recv(), if room => {
+                    waiting.entry(file.clone()).or_default().push(done);
+                    queue.push(file);
+                }
+                Some(finished) = running.join_next_with_id() => {
+                    let id = match &finished {
+                        Ok((id, ())) => *id,
+                        Err(e) => e.id(),
+                    };
+                    if let Some(file) = running_paths.remove(&id) {
+                        queue.done(&file);
+                        if let Err(e) = finished {
+                            eprintln!("Scan of {:?} failed: {}", file, e);
+                        }
+                    }
+                    if queue.is_empty() && running.is_empty() {
+                        let monitor = monitor.clone();
+                        tokio::spawn(async move { monitor.flush_cache().await });
                     }
                 }
+                Some(()) = reload_rx.recv() => monitor.reload_rules(),
+                _ = &mut baseline, if !baseline_done => baseline_done = true,
+            }
+
+            while running.len() < workers.max_workers.max(1) {
+                let Some(file) = queue.pop() else { break };
+                let waiters = waiting.remove(&file).unwrap_or_default();
+                let handle = running.spawn(Self::scan_job(monitor.clone(), file.clone(), waiters));
+                running_paths.insert(handle.id(), file);
             }
         }
 
         Ok(())
     }
 
+    /// One worker's scan of `path`, abandoned once it takes longer than
+    /// `WorkerConfig::timeout_secs`. Dropping `waiters` tells a waiting
+    /// baseline crawl the file is done.
+    async fn scan_job(monitor: Arc<Self>, path: PathBuf, waiters: Vec<oneshot::Sender<()>>) {
+        let timeout = monitor.config.workers.timeout();
+        match tokio::time::timeout(timeout, monitor.scan_file(&path)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => eprintln!("Error scanning file {:?}: {}", path, e),
+            Err(_) => eprintln!("Scan of {:?} timed out after {:?}", path, timeout),
+        }
+        drop(waiters);
+    }
+
+    /// Crawls each of `roots` at startup and then on
+    /// `BaselineConfig::interval_secs`, resuming an interrupted crawl where
+    /// it stopped. Roots are crawled one at a time, whichever is due first.
+    /// Files are sent on `files` for the scan workers.
+    async fn run_baseline(monitor: Arc<Self>, roots: Vec<PathBuf>, files: mpsc::Sender<CrawledFile>) {
+        let config = &monitor.config.baseline;
+        let mut crawled = HashSet::new();
+
+        loop {
+            let next = roots
+                .iter()
+                .filter_map(|root| Some((baseline::next_crawl_in(root, config, !crawled.contains(root))?, root)))
+                .min_by_key(|(delay, _)| *delay);
+            let Some((delay, root)) = next else { return };
+
+            tokio::time::sleep(delay).await;
+            crawled.insert(root);
+            if let Err(e) = Self::crawl(&monitor, root, &files).await {
+                eprintln!("Baseline scan of {:?} stopped: {}", root, e);
+                return;
+            }
+        }
+    }
+
+    /// Lists the files under `root` still to be scanned and sends them to
+    /// `files`, recording each as done once its scan ends.
+    async fn crawl(monitor: &Arc<Self>, root: &Path, files: &mpsc::Sender<CrawledFile>) -> Result<(), Box<dyn Error>> {
+        let lister = monitor.clone();
+        let listed = root.to_path_buf();
+        let mut crawl =
+            tokio::task::spawn_blocking(move || BaselineCrawl::start_scoped(&listed, &lister.config.baseline, &lister.scope)).await?;
+        println!("Baseline scan of {:?} started: {}", root, crawl.progress());
+        let throttle = monitor.config.baseline.throttle();
+        // Files are recorded as done in path order, so a restart never skips
+        // one that was still being scanned
+        let mut in_flight = VecDeque::new();
+
+        while let Some(file) = crawl.next_file() {
+            let (done_tx, done_rx) = oneshot::channel();
+            files.send((file.clone(), done_tx)).await.map_err(|_| "monitor stopped")?;
+            in_flight.push_back((file, done_rx));
+
+            while in_flight.len() > monitor.config.workers.max_workers {
+                let Some((file, done)) = in_flight.pop_front() else { break };
+                let _ = done.await;
+                crawl = Self::crawl_completed(crawl, file).await?;
+            }
+            if let Some(pause) = throttle {
+                tokio::time::sleep(pause).await;
+            }
+        }
+        for (file, done) in in_flight {
+            let _ = done.await;
+            crawl = Self::crawl_completed(crawl, file).await?;
+        }
+
+        tokio::task::spawn_blocking(move || crawl.finish().map_err(|e| e.to_string())).await??;
+        monitor.flush_cache().await;
+        Ok(())
+    }
+
+    async fn crawl_completed(mut crawl: BaselineCrawl, file: PathBuf) -> Result<BaselineCrawl, Box<dyn Error>> {
+        let crawl = tokio::task::spawn_blocking(move || crawl.completed(&file).map(|()| crawl).map_err(|e| e.to_string())).await??;
+        Ok(crawl)
+    }
+
+    fn reload_rules(&self) {
+        if let Some(rules) = &self.rules {
+            match rules.reload(&self.detector_ids()) {
+                Ok(count) => println!("Reloaded {} rules from {:?}", count, rules.path()),
+                Err(e) => eprintln!("Keeping previous rules, reload failed: {}", e),
+            }
+        }
+    }
+
+    /// Enough overlap between streamed chunks for the longest match any
+    /// detector can produce, and at least one byte so fingerprinting can tell
+    /// a chunk that ends mid-word.
+    fn chunk_overlap(&self, detectors: &[&dyn Detector]) -> usize {
+        let cap = self.config.scanner.text.max_overlap_bytes;
+        detectors
+            .iter()
+            .map(|detector| detector.max_match_len().unwrap_or(cap))
+            .max()
+            .unwrap_or(0)
+            .min(cap)
+            .max(1)
+    }
+
+    /// Writes out the scan cache if anything changed since it was last saved.
+    /// The entries are copied under the lock and written on the blocking
+    /// pool.
+    async fn flush_cache(&self) {
+        let Some(cache) = &self.cache else { return };
+        let _flushing = self.flushing.lock().await;
+        let snapshot = {
+            let cache = cache.lock().unwrap();
+            if cache.dirty() == 0 {
+                return;
+            }
+            cache.snapshot()
+        };
+        let written = tokio::task::spawn_blocking(move || snapshot.write().map(|()| snapshot).map_err(|e| e.to_string()))
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+        match written {
+            Ok(snapshot) => cache.lock().unwrap().saved(&snapshot),
+            Err(e) => eprintln!("Failed to save scan cache {:?}: {}", self.config.cache.path, e),
+        }
+    }
+
+    /// Scans `path` and sends its alerts, unless it is over the size limit or
+    /// of a type not allowed. With the scan cache enabled, a file
+    /// whose contents and detectors are unchanged since its last scan is
+    /// skipped, and a copy of an already scanned file reuses that file's
+    /// alerts.
     async fn scan_file(&self, path: &PathBuf) -> Result<(), Box<dyn Error>> {
-        println!("Scanning file: {:?}", path);
+        // Reads the file's first bytes to sniff its type
+        let (scope, checked) = (self.scope.clone(), path.clone());
+        if let Err(reason) = tokio::task::spawn_blocking(move || scope.check_content(&checked)).await? {
+            println!("Skipping {:?}: {}", path, reason);
+            return Ok(());
+        }
+        let Some(cache) = &self.cache else {
+            let alerts = self.scan_alerts(path).await?;
+            let alerts = self.quarantine(path, alerts).await;
+            return self.send_alerts(alerts).await;
+        };
 
-        let contents = self.content_scanner.scan(path).await?;
-        
-        for content in contents {
-            for pattern in &self.patterns {
-                if let Some(matched) = pattern.find(&content) {
-                    self.comm.send_alert(Alert {
-                        device_id: self.comm.device_id.clone(),
+        let version = self.detector_version();
+        let stamp = FileStamp::of(path)?;
+        if cache.lock().unwrap().is_unchanged(path, stamp, &version) {
+            println!("Skipping unchanged file: {:?}", path);
+            return Ok(());
+        }
+        // Hashed without holding the cache, which every worker needs
+        let hashed = path.clone();
+        let content_hash = tokio::task::spawn_blocking(move || cache::hash_file(&hashed)).await??;
+        let check = cache.lock().unwrap().check_content(path, stamp, content_hash, &version);
+        let (content_hash, alerts) = match check {
+            CacheCheck::Unchanged => {
+                println!("Skipping unchanged file: {:?}", path);
+                return Ok(());
+            }
+            CacheCheck::Duplicate { of, content_hash, alerts } => {
+                println!("Reusing scan of identical file {:?} for {:?}", of, path);
+                let alerts = alerts
+                    .into_iter()
+                    .map(|alert| Alert {
                         file_path: path.to_string_lossy().to_string(),
-                        pattern_type: pattern.to_string(),
-                        matched_content: matched.as_str().to_string(),
                         timestamp: chrono::Utc::now(),
-                    }).await?;
-                }
+                        ..alert
+                    })
+                    .collect();
+                (content_hash, alerts)
             }
+            CacheCheck::Changed { content_hash } => (content_hash, self.scan_alerts(path).await?),
+        };
+
+        // Recorded before sending: a failed delivery is retried by
+        // `Communication`, not by scanning again
+        let due = {
+            let mut cache = cache.lock().unwrap();
+            cache.insert(path, stamp, content_hash, &version, alerts.clone());
+            cache.dirty() >= self.config.cache.flush_every
+        };
+        if due {
+            self.flush_cache().await;
+        }
+        let alerts = self.quarantine(path, alerts).await;
+        self.send_alerts(alerts).await
+    }
+
+    /// Moves the file into quarantine if its alerts are severe enough, and
+    /// tags the alerts with where it went. A failed move is logged and the
+    /// alerts are sent as they are, as are those of a file restored from
+    /// quarantine and unchanged since.
+    async fn quarantine(&self, path: &Path, alerts: Vec<Alert>) -> Vec<Alert> {
+        let store = Quarantine::new(self.config.quarantine.clone());
+        if !store.applies(&alerts) {
+            return alerts;
+        }
+        let file = path.to_path_buf();
+        let findings = alerts.clone();
+        let moved = tokio::task::spawn_blocking(move || {
+            if store.was_restored(&file) {
+                return Ok(None);
+            }
+            store.quarantine(&file, &findings).map(Some).map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|moved| moved);
+        match moved {
+            Ok(None) => {
+                println!("Leaving restored file {:?} in place", path);
+                alerts
+            }
+            Ok(Some(manifest)) => {
+                println!("Quarantined {:?} as {}", path, manifest.id);
+                alerts
+                    .into_iter()
+                    .map(|mut alert| {
+                        alert.metadata.insert("quarantine_id".to_string(), manifest.id.clone());
+                        alert
+                    })
+                    .collect()
+            }
+            Err(e) => {
+                eprintln!("Failed to quarantine {:?}: {}", path, e);
+                alerts
+            }
+        }
+    }
+
+    async fn send_alerts(&self, alerts: Vec<Alert>) -> Result<(), Box<dyn Error>> {
+        for alert in alerts {
+            self.comm.send_alert(alert).await?;
         }
-        
         Ok(())
     }
+
+    async fn scan_alerts(&self, path: &PathBuf) -> Result<Vec<Alert>, Box<dyn Error>> {
+        println!("Scanning file: {:?}", path);
+        let mut alerts = Vec::new();
+
+        // First finding and every location, grouped per detector
+        let mut matches: BTreeMap<usize, (Finding, Vec<MatchLocation>)> = BTreeMap::new();
+
+        let rules = self.rules.as_ref().map(RuleSet::detectors).unwrap_or_default();
+        let detectors: Vec<&dyn Detector> = self.detectors.iter().chain(rules.iter()).map(|d| d.as_ref()).collect();
+        let decoding = &self.config.decoding;
+        // Exact data matches only count once a whole file has been read
+        let mut edm_hits: Vec<(EdmHit, String, MatchLocation)> = Vec::new();
+        let mut shingler = self.fingerprints.as_ref().map(FingerprintIndex::shingler);
+        let mut shingles = HashSet::new();
+        // Raw bytes past what the text scan reads are not evaluated either
+        let yara_bytes = self.config.yara_max_bytes.min(self.config.scanner.text.max_bytes_per_file);
+        let yara = self.yara.clone().map(|rules| YaraTask::spawn(rules, path.clone(), yara_bytes));
+
+        // Matching stays here on the async worker rather than the blocking
+        // pool. Text and CSV files arrive `chunk_bytes` at a time with a
+        // yield between reads, so a scan past `WorkerConfig::timeout_secs`
+        // can still be abandoned there; work on the blocking pool cannot.
+        let violations = self.content_scanner.scan_each(path, self.chunk_overlap(&detectors), |content| {
+            let payloads = decode_payloads(&content.text, decoding.max_depth, decoding.min_blob_len);
+
+            let plain = detect_all(&detectors, path, &content.origin, &content.text);
+            for (index, finding) in &plain {
+                if !content.owns(finding.start) || finding.confidence < self.config.min_confidence {
+                    continue;
+                }
+                let location = MatchLocation::locate(&content, finding, self.config.context_chars);
+                matches.entry(*index).or_insert_with(|| (finding.clone(), Vec::new())).1.push(location);
+            }
+
+            // Decoded matches are located at the blob they were hidden in
+            for payload in payloads.iter().filter(|p| content.owns(p.start)) {
+                for (index, finding) in detect_all(&detectors, path, &content.origin, &payload.text) {
+                    if finding.confidence < self.config.min_confidence {
+                        continue;
+                    }
+                    // A value left readable in the blob, as around a lone %0A, was reported above
+                    let readable = plain.iter().any(|(plain_index, plain)| {
+                        *plain_index == index && plain.matched == finding.matched && payload.start <= plain.start && plain.end <= payload.end
+                    });
+                    if readable {
+                        continue;
+                    }
+                    let mut blob = Finding::new(&finding.detector_id, &content.text[payload.start..payload.end], payload.start, payload.end);
+                    blob.confidence = finding.confidence;
+                    let mut location = MatchLocation::locate(&content, &blob, self.config.context_chars);
+                    location.encoding_chain = payload.chain.clone();
+                    matches.entry(index).or_insert_with(|| (finding, Vec::new())).1.push(location);
+                }
+            }
+
+            if let Some(edm) = &self.edm {
+                for hit in edm.find(&content.text).into_iter().filter(|hit| content.owns(hit.start)) {
+                    let mut finding = Finding::new("EXACT_DATA_MATCH", &content.text[hit.start..hit.end], hit.start, hit.end);
+                    finding.confidence = Confidence::High;
+                    let location = MatchLocation::locate(&content, &finding, self.config.context_chars);
+                    edm_hits.push((hit, finding.matched, location));
+                }
+            }
+
+            if let Some(yara) = &yara {
+                yara.scan_text(&content.text, content.owned_len);
+            }
+
+            if let (Some(index), Some(shingler)) = (&self.fingerprints, shingler.as_mut()) {
+                shingler.feed_content(&content);
+                shingles.extend(shingler.take().into_iter().filter(|&s| index.contains(s)));
+            }
+        }).await?;
+
+        for (index, (first, locations)) in matches {
+            if locations.len() < self.config.min_match_count {
+                continue;
+            }
+            let detector = detectors[index];
+            let confidence = locations.iter().map(|l| l.confidence).max().unwrap_or_default();
+
+            alerts.push(Alert {
+                device_id: self.comm.device_id.clone(),
+                file_path: path.to_string_lossy().to_string(),
+                pattern_type: detector.id().to_string(),
+                detector_name: detector.name().to_string(),
+                category: detector.category(),
+                severity: detector.severity(),
+                confidence,
+                matched_content: self.config.redaction.redact(&first.matched),
+                redaction: self.config.redaction.applied_mode(),
+                match_count: locations.len(),
+                locations,
+                metadata: first.metadata,
+                timestamp: chrono::Utc::now(),
+            });
+        }
+
+        if let Some(edm) = &self.edm {
+            alerts.extend(self.edm_alert(path, edm, edm_hits));
+        }
+
+        if let (Some(index), Some(mut shingler)) = (&self.fingerprints, shingler) {
+            shingler.finish();
+            shingles.extend(shingler.take().into_iter().filter(|&s| index.contains(s)));
+            for document in index.matches(&shingles) {
+                alerts.push(self.fingerprint_alert(path, document));
+            }
+        }
+
+        let yara_hits = match yara {
+            Some(yara) => yara.finish().await?,
+            None => YaraHits::default(),
+        };
+        for (rule, scanned) in yara_hits.into_matches() {
+            alerts.push(self.yara_alert(path, rule, scanned.into_iter().collect()));
+        }
+
+        for violation in violations {
+            let mut metadata = BTreeMap::new();
+            metadata.insert("reason".to_string(), violation.reason);
+            if let Some(entry) = violation.origin.entry {
+                metadata.insert("entry".to_string(), entry);
+            }
+
+            alerts.push(Alert {
+                device_id: self.comm.device_id.clone(),
+                file_path: path.to_string_lossy().to_string(),
+                pattern_type: "ARCHIVE_BOMB_SUSPECTED".to_string(),
+                detector_name: "Archive bomb suspected".to_string(),
+                category: Category::Anomaly,
+                severity: Severity::High,
+                confidence: Confidence::High,
+                matched_content: String::new(),
+                redaction: self.config.redaction.applied_mode(),
+                match_count: 1,
+                locations: Vec::new(),
+                metadata,
+                timestamp: chrono::Utc::now(),
+            });
+        }
+
+        Ok(alerts)
+    }
+
+    fn edm_alert(&self, path: &Path, edm: &EdmIndex, hits: Vec<(EdmHit, String, MatchLocation)>) -> Option<Alert> {
+        let records = edm.co_occurring(&hits.iter().map(|(hit, _, _)| *hit).collect::<Vec<_>>());
+        if records.is_empty() {
+            return None;
+        }
+
+        let matched: BTreeMap<u32, _> = records.iter().map(|r| (r.record, &r.fields)).collect();
+        let fields: BTreeSet<&str> = records
+            .iter()
+            .flat_map(|r| r.fields.iter().filter_map(|&f| edm.fields.get(f as usize)).map(String::as_str))
+            .collect();
+        let mut first = None;
+        let mut locations: Vec<MatchLocation> = Vec::new();
+        for (hit, value, location) in hits {
+            if !matched.get(&hit.record).is_some_and(|fields| fields.contains(&hit.field)) {
+                continue;
+            }
+            first.get_or_insert(value);
+            if !locations.iter().any(|l| l.offset == location.offset && l.origin == location.origin) {
+                locations.push(location);
+            }
+        }
+
+        let mut metadata = BTreeMap::new();
+        metadata.insert("records".to_string(), records.len().to_string());
+        metadata.insert("fields".to_string(), fields.into_iter().collect::<Vec<_>>().join(", "));
+
+        Some(Alert {
+            device_id: self.comm.device_id.clone(),
+            file_path: path.to_string_lossy().to_string(),
+            pattern_type: "EXACT_DATA_MATCH".to_string(),
+            detector_name: "Exact data match".to_string(),
+            category: Category::Pii,
+            severity: Severity::Critical,
+            confidence: Confidence::High,
+            matched_content: self.config.redaction.redact(&first.unwrap_or_default()),
+            redaction: self.config.redaction.applied_mode(),
+            match_count: records.len(),
+            locations,
+            metadata,
+            timestamp: chrono::Utc::now(),
+        })
+    }
+
+    fn fingerprint_alert(&self, path: &Path, document: DocumentMatch) -> Alert {
+        let mut metadata = BTreeMap::new();
+        metadata.insert("document".to_string(), document.document);
+        metadata.insert("similarity".to_string(), format!("{:.2}", document.similarity));
+
+        Alert {
+            device_id: self.comm.device_id.clone(),
+            file_path: path.to_string_lossy().to_string(),
+            pattern_type: "DOCUMENT_FINGERPRINT".to_string(),
+            detector_name: "Confidential document copy".to_string(),
+            category: Category::Confidential,
+            severity: Severity::High,
+            confidence: Confidence::High,
+            matched_content: String::new(),
+            redaction: self.config.redaction.applied_mode(),
+            match_count: document.shared_shingles,
+            locations: Vec::new(),
+            metadata,
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    fn yara_alert(&self, path: &Path, rule: YaraMatch, scanned: Vec<&str>) -> Alert {
+        let mut metadata = BTreeMap::new();
+        metadata.insert("rule".to_string(), rule.rule.clone());
+        metadata.insert("namespace".to_string(), rule.namespace.clone());
+        metadata.insert("tags".to_string(), rule.tags.join(", "));
+        metadata.insert("strings".to_string(), rule.strings.join(", "));
+        metadata.insert("scanned".to_string(), scanned.join(", "));
+        for (key, value) in &rule.metadata {
+            metadata.insert(format!("meta.{}", key), value.clone());
+        }
+
+        Alert {
+            device_id: self.comm.device_id.clone(),
+            file_path: path.to_string_lossy().to_string(),
+            pattern_type: "YARA_MATCH".to_string(),
+            detector_name: rule.rule.clone(),
+            category: Category::Custom,
+            severity: rule.severity().unwrap_or(Severity::High),
+            confidence: Confidence::High,
+            matched_content: self.config.redaction.redact(&String::from_utf8_lossy(&rule.first_match)),
+            redaction: self.config.redaction.applied_mode(),
+            match_count: rule.match_count,
+            locations: Vec::new(),
+            metadata,
+            timestamp: chrono::Utc::now(),
+        }
+    }
 }
\ No newline at end of file
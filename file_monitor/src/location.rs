@@ -0,0 +1,56 @@
+use serde::{Serialize, Deserialize};
+use crate::decoding::PayloadEncoding;
+use crate::detectors::{Confidence, Finding};
+use crate::redaction::mask_alphanumeric;
+use crate::scanner::{ContentOrigin, ScannedContent};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct MatchLocation {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub origin: ContentOrigin,
+    pub context: String,
+    pub confidence: Confidence,
+    /// Encodings the match was hidden under, outermost first; empty when it
+    /// appeared as literal text.
+    pub encoding_chain: Vec<PayloadEncoding>,
+}
+
+impl MatchLocation {
+    /// Positions `finding` within the file `content` was taken from. Line and
+    /// column are 1-based and the column counts characters, not bytes. The context keeps up to
+    /// `context_chars` characters on each side, with the match itself
+    /// replaced by the detector id. Letters and digits around it are masked
+    /// too, since neighbouring values may be just as sensitive, so the
+    /// snippet shows only the shape of the text.
+    pub fn locate(content: &ScannedContent, finding: &Finding, context_chars: usize) -> Self {
+        let text = &content.text;
+        let before = &text[..finding.start];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+        let prefix_start = before
+            .char_indices()
+            .rev()
+            .nth(context_chars.saturating_sub(1))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        let prefix = if context_chars == 0 { "" } else { &before[prefix_start..] };
+        let suffix: String = text[finding.end..].chars().take(context_chars).collect();
+        let context = format!("{}[{}]{}", mask_alphanumeric(prefix), finding.detector_id, mask_alphanumeric(&suffix));
+
+        // Chunks of a streamed file may start part way through a line
+        let start = &content.start;
+        let column = before[line_start..].chars().count() + if line_start == 0 { start.column } else { 0 };
+
+        Self {
+            offset: start.offset + finding.start,
+            line: start.line + before.matches('\n').count() + 1,
+            column: column + 1,
+            origin: content.origin_at(finding.start),
+            context,
+            confidence: finding.confidence,
+            encoding_chain: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use crate::detectors::Severity;
+use crate::Alert;
+
+const MANIFEST: &str = "manifest.json";
+const CONTENT: &str = "file";
+/// Restored files the monitor leaves alone, by original path.
+const RESTORED: &str = "restored.json";
+
+/// Moving files with serious findings out of users' reach.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct QuarantineConfig {
+    pub enabled: bool,
+    /// The store; created readable by its owner only.
+    pub dir: PathBuf,
+    /// Files with an alert at or above this severity are quarantined.
+    pub min_severity: Severity,
+    /// Leave a note at `<file>.quarantined.txt` saying where the file went.
+    pub tombstone: bool,
+}
+
+impl Default for QuarantineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: PathBuf::from("quarantine"),
+            min_severity: Severity::Critical,
+            tombstone: true,
+        }
+    }
+}
+
+/// Unix owner of a quarantined file.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct FileOwner {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// Everything needed to put a quarantined file back as it was.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuarantineManifest {
+    pub id: String,
+    pub original_path: PathBuf,
+    pub owner: Option<FileOwner>,
+    /// Unix permission bits.
+    pub mode: Option<u32>,
+    pub readonly: bool,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// Hex SHA-256 of the contents, checked again on restore.
+    pub sha256: String,
+    /// The alerts that caused the quarantine.
+    pub findings: Vec<Alert>,
+    pub tombstone: Option<PathBuf>,
+    pub quarantined_at: DateTime<Utc>,
+}
+
+/// Quarantine store: one directory per file, holding the file and its
+/// manifest.
+pub struct Quarantine {
+    config: QuarantineConfig,
+}
+
+impl Quarantine {
+    pub fn new(config: QuarantineConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether `alerts` call for quarantining their file.
+    pub fn applies(&self, alerts: &[Alert]) -> bool {
+        self.config.enabled && alerts.iter().any(|alert| alert.severity >= self.config.min_severity)
+    }
+
+    /// Moves `path` into the store and records `findings` with it. The
+    /// manifest is written first, so a file in the store can always be
+    /// restored. Symlinks are refused.
+    pub fn quarantine(&self, path: &Path, findings: &[Alert]) -> Result<QuarantineManifest, Box<dyn Error>> {
+        // A symlink would be moved in place of the file it points to
+        let metadata = fs::symlink_metadata(path)?;
+        if !metadata.is_file() {
+            return Err(format!("{}: not a regular file", path.display()).into());
+        }
+        let original_path = fs::canonicalize(path)?;
+        let sha256 = hash_file(path)?;
+        let quarantined_at = Utc::now();
+        let id = format!("{}-{}", quarantined_at.format("%Y%m%dT%H%M%S%.3fZ"), &sha256[..12]);
+
+        create_private_dir(&self.config.dir)?;
+        let entry = self.config.dir.join(&id);
+        if entry.exists() {
+            return Err(format!("quarantine entry {} already exists", id).into());
+        }
+        create_private_dir(&entry)?;
+        let tombstone = self.config.tombstone.then(|| tombstone_path(&original_path));
+        let mut manifest = QuarantineManifest {
+            id,
+            original_path,
+            owner: owner(&metadata),
+            mode: mode(&metadata),
+            readonly: metadata.permissions().readonly(),
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            sha256,
+            findings: findings.to_vec(),
+            tombstone,
+            quarantined_at,
+        };
+        let moved = write_manifest(&entry, &manifest).and_then(|()| {
+            let stored = entry.join(CONTENT);
+            move_file(path, &stored)?;
+            if hash_file(&stored)? != manifest.sha256 {
+                move_file(&stored, path)?;
+                return Err(format!("{}: changed while being quarantined", path.display()).into());
+            }
+            Ok(())
+        });
+        if let Err(e) = moved {
+            if !entry.join(CONTENT).exists() {
+                let _ = fs::remove_dir_all(&entry);
+            }
+            return Err(e);
+        }
+
+        if let Some(note) = &manifest.tombstone {
+            let types: Vec<&str> = findings.iter().map(|alert| alert.pattern_type.as_str()).collect();
+            let text = format!(
+                "{} was quarantined on {} because it contains sensitive data ({}).\n\
+                 Quarantine id: {}\n\
+                 Contact your administrator if you need it restored.\n",
+                manifest.original_path.display(),
+                quarantined_at.to_rfc3339(),
+                types.join(", "),
+                manifest.id
+            );
+            if let Err(e) = fs::write(note, text) {
+                eprintln!("Failed to write quarantine note {:?}: {}", note, e);
+                manifest.tombstone = None;
+                if let Err(e) = write_manifest(&entry, &manifest) {
+                    eprintln!("Failed to update quarantine manifest {:?}: {}", entry, e);
+                }
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Quarantined files, oldest first.
+    pub fn list(&self) -> Result<Vec<QuarantineManifest>, Box<dyn Error>> {
+        let mut manifests = Vec::new();
+        let entries = match fs::read_dir(&self.config.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(manifests),
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                manifests.push(self.manifest(&entry.file_name().to_string_lossy())?);
+            }
+        }
+        manifests.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(manifests)
+    }
+
+    pub fn manifest(&self, id: &str) -> Result<QuarantineManifest, Box<dyn Error>> {
+        let path = self.entry(id)?.join(MANIFEST);
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Puts a quarantined file back where it was, with its permissions,
+    /// owner and modification time, and removes the note and the store
+    /// entry. Refuses if something else now exists at the original path or
+    /// the stored contents no longer match the manifest.
+    pub fn restore(&self, id: &str) -> Result<QuarantineManifest, Box<dyn Error>> {
+        let manifest = self.manifest(id)?;
+        let entry = self.entry(id)?;
+        let stored = entry.join(CONTENT);
+        let original = &manifest.original_path;
+
+        if original.symlink_metadata().is_ok() {
+            return Err(format!("{}: already exists, not overwriting", original.display()).into());
+        }
+        if hash_file(&stored)? != manifest.sha256 {
+            return Err(format!("{}: stored file does not match its manifest", id).into());
+        }
+
+        // Recorded before the file reappears, so a running monitor that sees
+        // it does not quarantine it again
+        self.allow_restored(original, &manifest.sha256)?;
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_file(&stored, original)?;
+        if let Some(modified) = manifest.modified {
+            fs::File::options().write(true).open(original)?.set_modified(modified)?;
+        }
+        restore_owner(original, manifest.owner)?;
+        let mut permissions = fs::metadata(original)?.permissions();
+        set_mode(&mut permissions, manifest.mode, manifest.readonly);
+        fs::set_permissions(original, permissions)?;
+
+        if let Some(note) = &manifest.tombstone {
+            if let Err(e) = fs::remove_file(note) {
+                eprintln!("Failed to remove quarantine note {:?}: {}", note, e);
+            }
+        }
+        fs::remove_dir_all(entry)?;
+        Ok(manifest)
+    }
+
+    /// Whether `path` was restored from the store and still has the
+    /// contents it was restored with. Such a file is left in place until it
+    /// changes.
+    pub fn was_restored(&self, path: &Path) -> bool {
+        let Ok(original) = fs::canonicalize(path) else { return false };
+        match self.restored().get(&original) {
+            Some(sha256) => hash_file(&original).is_ok_and(|current| &current == sha256),
+            None => false,
+        }
+    }
+
+    fn restored(&self) -> HashMap<PathBuf, String> {
+        fs::read_to_string(self.config.dir.join(RESTORED))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn allow_restored(&self, original: &Path, sha256: &str) -> Result<(), Box<dyn Error>> {
+        let mut restored = self.restored();
+        restored.insert(original.to_path_buf(), sha256.to_string());
+        let path = self.config.dir.join(RESTORED);
+        let temp = path.with_extension("json.partial");
+        fs::write(&temp, serde_json::to_string_pretty(&restored)?)?;
+        fs::rename(&temp, &path)?;
+        Ok(())
+    }
+
+    fn entry(&self, id: &str) -> Result<PathBuf, Box<dyn Error>> {
+        if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+            return Err(format!("invalid quarantine id {:?}", id).into());
+        }
+        Ok(self.config.dir.join(id))
+    }
+}
+
+fn write_manifest(entry: &Path, manifest: &QuarantineManifest) -> Result<(), Box<dyn Error>> {
+    fs::write(entry.join(MANIFEST), serde_json::to_string_pretty(manifest)?)?;
+    Ok(())
+}
+
+/// Where the note for a file quarantined from `path` is written.
+pub fn tombstone_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".quarantined.txt");
+    path.with_file_name(name)
+}
+
+/// Renames when source and destination share a file system, and copies
+/// then deletes otherwise.
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::File::open(to)?.sync_all()?;
+    fs::remove_file(from)
+}
+
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)
+}
+
+#[cfg(unix)]
+fn owner(metadata: &fs::Metadata) -> Option<FileOwner> {
+    use std::os::unix::fs::MetadataExt;
+    Some(FileOwner { uid: metadata.uid(), gid: metadata.gid() })
+}
+
+#[cfg(not(unix))]
+fn owner(_metadata: &fs::Metadata) -> Option<FileOwner> {
+    None
+}
+
+#[cfg(unix)]
+fn mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+/// Only root may give a file away; anyone else keeps the file and the
+/// original owner's ids are left in the manifest.
+#[cfg(unix)]
+fn restore_owner(path: &Path, owner: Option<FileOwner>) -> std::io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let Some(owner) = owner else { return Ok(()) };
+    let current = fs::metadata(path)?;
+    if (current.uid(), current.gid()) == (owner.uid, owner.gid) {
+        return Ok(());
+    }
+    match std::os::unix::fs::chown(path, Some(owner.uid), Some(owner.gid)) {
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            eprintln!("Restored {:?} without its owner ({}:{}): {}", path, owner.uid, owner.gid, e);
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+#[cfg(not(unix))]
+fn restore_owner(_path: &Path, _owner: Option<FileOwner>) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_mode(permissions: &mut fs::Permissions, mode: Option<u32>, readonly: bool) {
+    use std::os::unix::fs::PermissionsExt;
+    match mode {
+        Some(mode) => permissions.set_mode(mode),
+        None => permissions.set_readonly(readonly),
+    }
+}
+
+#[cfg(not(unix))]
+fn set_mode(permissions: &mut fs::Permissions, _mode: Option<u32>, readonly: bool) {
+    permissions.set_readonly(readonly);
+}
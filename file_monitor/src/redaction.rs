@@ -0,0 +1,75 @@
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedactionMode {
+    /// Replace the whole value with a fixed mask that does not reveal its length.
+    FullMask,
+    /// Mask everything except the last four letters or digits.
+    #[default]
+    KeepLast4,
+    /// Replace the value with a salted SHA-256 token so repeats can still be
+    /// correlated. Needs a salt: without one, values with few possibilities,
+    /// like SSNs, can be recovered by hashing every candidate.
+    SaltedHash,
+    /// Mask letters and digits but keep separators, e.g. `***-**-****`.
+    FormatPreserving,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RedactionConfig {
+    pub mode: RedactionMode,
+    pub salt: String,
+}
+
+impl RedactionConfig {
+    /// Rejects `SaltedHash` without a salt.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode == RedactionMode::SaltedHash && self.salt.is_empty() {
+            return Err("redaction.salt must be set to use SaltedHash".to_string());
+        }
+        Ok(())
+    }
+
+    /// The mode `redact` applies, recorded with each alert: `SaltedHash`
+    /// without a salt masks values fully rather than emit an unsalted hash.
+    pub fn applied_mode(&self) -> RedactionMode {
+        match self.mode {
+            RedactionMode::SaltedHash if self.salt.is_empty() => RedactionMode::FullMask,
+            mode => mode,
+        }
+    }
+
+    /// Masks `value` with `applied_mode`.
+    pub fn redact(&self, value: &str) -> String {
+        match self.applied_mode() {
+            RedactionMode::FullMask => "********".to_string(),
+            RedactionMode::KeepLast4 => {
+                let keep_from = value
+                    .char_indices()
+                    .filter(|(_, c)| c.is_alphanumeric())
+                    .map(|(i, _)| i)
+                    .rev()
+                    .nth(3)
+                    .unwrap_or(value.len());
+                mask_alphanumeric(&value[..keep_from]) + &value[keep_from..]
+            }
+            RedactionMode::SaltedHash => {
+                let mut hasher = Sha256::new();
+                hasher.update(self.salt.as_bytes());
+                hasher.update(value.as_bytes());
+                let hex: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+                format!("sha256:{}", hex)
+            }
+            RedactionMode::FormatPreserving => mask_alphanumeric(value),
+        }
+    }
+}
+
+pub(crate) fn mask_alphanumeric(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_alphanumeric() { '*' } else { c })
+        .collect()
+}
@@ -0,0 +1,97 @@
+use serde::{Serialize, Deserialize};
+use crate::detectors::{
+    from_specs, Category, Confidence, Detector, DetectorSpec, KeywordRule, KeywordSpec, RegexDetector, Severity,
+    DEFAULT_KEYWORD_WINDOW,
+};
+use crate::validators::Validator;
+
+/// Optional detector sets for national identifiers, enabled per policy
+/// group through `MonitorConfig::policy_groups`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectorPack {
+    India,
+    Uk,
+    Eu,
+    Passports,
+}
+
+impl DetectorPack {
+    pub fn detectors(&self) -> Vec<Box<dyn Detector>> {
+        match self {
+            Self::India => india_detectors(),
+            Self::Uk => uk_detectors(),
+            Self::Eu => eu_detectors(),
+            Self::Passports => passport_detectors(),
+        }
+    }
+}
+
+const REGIONAL_KEYWORDS: [KeywordSpec; 8] = [
+    ("AADHAAR", &["aadhaar", "aadhar", "uidai", "uid"]),
+    ("IN_PAN", &["pan", "permanent account", "income tax"]),
+    ("IN_GSTIN", &["gst"]),
+    ("UK_NATIONAL_INSURANCE", &["national insurance", "nino", "ni number", "ni no"]),
+    ("EU_IBAN", &["iban", "bank", "account", "konto", "compte", "cuenta"]),
+    ("DE_ID_CARD", &["personalausweis", "ausweis", "id card", "identity card"]),
+    ("FR_NIR", &["sécurité sociale", "securite sociale", "nir", "insee", "sécu"]),
+    ("ES_DNI", &["dni", "nie", "documento nacional", "extranjero"]),
+];
+
+pub fn india_detectors() -> Vec<Box<dyn Detector>> {
+    let specs: [DetectorSpec; 3] = [
+        ("AADHAAR", "Aadhaar number", Category::Pii, Severity::High, Confidence::Medium,
+            r"\b[2-9]\d{3}[ -]?\d{4}[ -]?\d{4}\b", Some(Validator::Aadhaar)),
+        ("IN_PAN", "Indian Permanent Account Number", Category::Pii, Severity::High, Confidence::Medium,
+            r"\b[A-Z]{3}[ABCFGHJLPT][A-Z]\d{4}[A-Z]\b", None),
+        ("IN_GSTIN", "Indian GST identification number", Category::Financial, Severity::Medium, Confidence::High,
+            r"\b\d{2}[A-Z]{3}[ABCFGHJLPT][A-Z]\d{4}[A-Z][1-9A-Z]Z[0-9A-Z]\b", Some(Validator::Gstin)),
+    ];
+    from_specs(&specs, &REGIONAL_KEYWORDS)
+}
+
+pub fn uk_detectors() -> Vec<Box<dyn Detector>> {
+    let specs: [DetectorSpec; 1] = [
+        ("UK_NATIONAL_INSURANCE", "UK National Insurance number", Category::Pii, Severity::High, Confidence::Medium,
+            r"\b[A-CEGHJ-PR-TW-Z][A-CEGHJ-NPR-TW-Z] ?\d{2} ?\d{2} ?\d{2} ?[A-D]\b", Some(Validator::UkNino)),
+    ];
+    from_specs(&specs, &REGIONAL_KEYWORDS)
+}
+
+pub fn eu_detectors() -> Vec<Box<dyn Detector>> {
+    let specs: [DetectorSpec; 4] = [
+        ("EU_IBAN", "EU bank account number (IBAN)", Category::Financial, Severity::High, Confidence::Medium,
+            r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b", Some(Validator::EuIban)),
+        ("DE_ID_CARD", "German identity card number", Category::Pii, Severity::High, Confidence::Medium,
+            r"\b[CFGHJKLMNPRTVWXYZ][CFGHJKLMNPRTVWXYZ0-9]{8}\d\b", Some(Validator::DeIdCard)),
+        ("FR_NIR", "French social security number (NIR)", Category::Pii, Severity::High, Confidence::Medium,
+            r"\b[12] ?\d{2} ?(?:0[1-9]|1[0-2]|[2-9]\d) ?(?:\d{2}|2A|2B) ?\d{3} ?\d{3} ?\d{2}\b", Some(Validator::FrNir)),
+        ("ES_DNI", "Spanish DNI or NIE", Category::Pii, Severity::High, Confidence::Medium,
+            r"\b(?:\d{8}|[XYZ]-?\d{7})-?[A-Z]\b", Some(Validator::EsDni)),
+    ];
+    from_specs(&specs, &REGIONAL_KEYWORDS)
+}
+
+/// Passport numbers have no checksum in their printed form and most formats
+/// are indistinguishable from other references, so a word such as
+/// "passport" must appear nearby.
+pub fn passport_detectors() -> Vec<Box<dyn Detector>> {
+    let specs = [
+        ("IN_PASSPORT", "Indian passport number", r"\b[A-PR-WY][1-9]\d ?\d{4}[1-9]\b"),
+        ("UK_PASSPORT", "UK passport number", r"\b\d{9}\b"),
+        ("DE_PASSPORT", "German passport number", r"\b[CFGHJK][CFGHJKLMNPRTVWXYZ0-9]{8}\b"),
+        ("FR_PASSPORT", "French passport number", r"\b\d{2}[A-Z]{2}\d{5}\b"),
+        ("ES_PASSPORT", "Spanish passport number", r"\b[A-Z]{3}\d{6}\b"),
+    ];
+    let keywords = ["passport", "passeport", "reisepass", "pasaporte"];
+
+    specs
+        .iter()
+        .map(|(id, name, pattern)| {
+            let detector = RegexDetector::new(id, name, Category::Pii, Severity::High, pattern)
+                .unwrap()
+                .with_keywords(KeywordRule::new(&keywords, DEFAULT_KEYWORD_WINDOW).required());
+            Box::new(detector) as Box<dyn Detector>
+        })
+        .collect()
+}
@@ -0,0 +1,276 @@
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+use crate::cache::digest;
+use crate::detectors::{Category, Confidence, Detector, KeywordRule, RegexDetector, Severity, DEFAULT_KEYWORD_WINDOW};
+use crate::validators::Validator;
+
+/// One detector as written in a rule file.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSpec {
+    /// Reported as the alert's `pattern_type`; upper case, digits and `_`.
+    pub id: String,
+    /// Display name, defaulting to the id.
+    pub name: Option<String>,
+    pub pattern: String,
+    pub severity: Severity,
+    #[serde(default = "default_category")]
+    pub category: Category,
+    #[serde(default)]
+    pub confidence: Confidence,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    pub keyword_window: Option<usize>,
+    /// Drop matches with no keyword nearby instead of lowering confidence.
+    #[serde(default)]
+    pub keywords_required: bool,
+    pub validator: Option<Validator>,
+    /// File extensions the rule applies to, e.g. `["csv", "xlsx"]`. Empty
+    /// means every file.
+    #[serde(default)]
+    pub file_types: Vec<String>,
+}
+
+fn default_category() -> Category {
+    Category::Custom
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    #[serde(default)]
+    rules: Vec<RuleSpec>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleFormat {
+    Toml,
+    Yaml,
+}
+
+impl RuleFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// A rule that failed validation.
+struct RuleError {
+    /// Position of the rule in the file, from 0.
+    index: usize,
+    id: String,
+    message: String,
+}
+
+/// Parses and validates a rule file's contents. Every invalid rule is
+/// reported, each starting on a new line with `rule <n> (<id>):`, rather
+/// than only the first.
+pub fn parse_rules(text: &str, format: RuleFormat) -> Result<Vec<Box<dyn Detector>>, Box<dyn Error>> {
+    let (detectors, errors) = build_rules(&parse_rule_file(text, format)?, &[]);
+    if errors.is_empty() {
+        return Ok(detectors);
+    }
+    let errors: Vec<String> = errors.iter().map(|e| format!("rule {} ({}): {}", e.index + 1, e.id, e.message)).collect();
+    Err(errors.join("\n").into())
+}
+
+/// Reads a `.toml`, `.yaml` or `.yml` rule file. Rules may not reuse the id
+/// of a detector in `reserved`. Each invalid rule is reported on its own
+/// line as `<path>:<line>: rule <n> (<id>):`, at the line of its `id`.
+pub fn load_rules(path: &Path, reserved: &[&str]) -> Result<Vec<Box<dyn Detector>>, Box<dyn Error>> {
+    let (text, format) = read_rule_file(path)?;
+    validate_rule_file(path, &text, format, reserved)
+}
+
+fn validate_rule_file(path: &Path, text: &str, format: RuleFormat, reserved: &[&str]) -> Result<Vec<Box<dyn Detector>>, Box<dyn Error>> {
+    let file = parse_rule_file(text, format).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let (detectors, errors) = build_rules(&file, reserved);
+    if errors.is_empty() {
+        return Ok(detectors);
+    }
+
+    let lines = id_lines(text);
+    let errors: Vec<String> = errors
+        .iter()
+        .map(|e| {
+            let at = match lines.get(e.index) {
+                Some(line) => format!("{}:{}", path.display(), line),
+                None => path.display().to_string(),
+            };
+            format!("{}: rule {} ({}): {}", at, e.index + 1, e.id, e.message)
+        })
+        .collect();
+    Err(errors.join("\n").into())
+}
+
+fn parse_rule_file(text: &str, format: RuleFormat) -> Result<RuleFile, Box<dyn Error>> {
+    Ok(match format {
+        RuleFormat::Toml => toml::from_str(text)?,
+        RuleFormat::Yaml => serde_yaml::from_str(text)?,
+    })
+}
+
+fn build_rules(file: &RuleFile, reserved: &[&str]) -> (Vec<Box<dyn Detector>>, Vec<RuleError>) {
+    let mut errors = Vec::new();
+    let mut ids = HashSet::new();
+    let mut detectors: Vec<Box<dyn Detector>> = Vec::new();
+    for (index, spec) in file.rules.iter().enumerate() {
+        let error = |message: String| RuleError { index, id: spec.id.clone(), message };
+        if reserved.contains(&spec.id.as_str()) {
+            errors.push(error("id is already used by another detector".to_string()));
+            continue;
+        }
+        if !ids.insert(spec.id.as_str()) {
+            errors.push(error("duplicate id".to_string()));
+            continue;
+        }
+        match build_rule(spec) {
+            Ok(detector) => detectors.push(Box::new(detector)),
+            Err(e) => errors.push(error(e)),
+        }
+    }
+
+    (detectors, errors)
+}
+
+/// Line number of each rule's `id` key, in file order.
+fn id_lines(text: &str) -> Vec<usize> {
+    let key = Regex::new(r#"^\s*(?:-\s+)?["']?id["']?\s*[:=]"#).unwrap();
+    text.lines().enumerate().filter(|(_, line)| key.is_match(line)).map(|(i, _)| i + 1).collect()
+}
+
+fn read_rule_file(path: &Path) -> Result<(String, RuleFormat), Box<dyn Error>> {
+    let format = RuleFormat::from_path(path)
+        .ok_or_else(|| format!("{}: rule files must end in .toml, .yaml or .yml", path.display()))?;
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok((text, format))
+}
+
+/// The rules in one version of a rule file.
+struct LoadedRules {
+    detectors: Arc<Vec<Box<dyn Detector>>>,
+    /// Digest of the file's contents.
+    digest: String,
+}
+
+impl LoadedRules {
+    fn read(path: &Path, reserved: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let (text, format) = read_rule_file(path)?;
+        let detectors = validate_rule_file(path, &text, format, reserved)?;
+        Ok(Self {
+            detectors: Arc::new(detectors),
+            digest: digest(text.as_bytes()),
+        })
+    }
+}
+
+fn build_rule(spec: &RuleSpec) -> Result<RegexDetector, String> {
+    if spec.id.is_empty() || !spec.id.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_') {
+        return Err("id must be upper case letters, digits and underscores".to_string());
+    }
+    let name = spec.name.as_deref().unwrap_or(&spec.id);
+    let detector = RegexDetector::new(&spec.id, name, spec.category, spec.severity, &spec.pattern)
+        .map_err(|e| format!("invalid pattern: {}", e))?;
+    if regex::Regex::new(&spec.pattern).is_ok_and(|r| r.is_match("")) {
+        return Err("pattern matches the empty string".to_string());
+    }
+    if spec.keywords_required && spec.keywords.is_empty() {
+        return Err("keywords_required is set but no keywords are given".to_string());
+    }
+    if spec.keyword_window == Some(0) {
+        return Err("keyword_window must be at least 1".to_string());
+    }
+
+    let mut detector = detector.with_confidence(spec.confidence);
+    if !spec.keywords.is_empty() {
+        let keywords: Vec<&str> = spec.keywords.iter().map(String::as_str).collect();
+        let mut rule = KeywordRule::new(&keywords, spec.keyword_window.unwrap_or(DEFAULT_KEYWORD_WINDOW));
+        if spec.keywords_required {
+            rule = rule.required();
+        }
+        detector = detector.with_keywords(rule);
+    }
+    if let Some(validator) = spec.validator {
+        detector = detector.with_validator(validator);
+    }
+    if !spec.file_types.is_empty() {
+        let file_types: Vec<&str> = spec.file_types.iter().map(String::as_str).collect();
+        detector = detector.with_file_types(&file_types);
+    }
+    Ok(detector)
+}
+
+/// Detectors loaded from a rule file, swapped out in place when the file
+/// changes so a running monitor picks them up without a restart.
+pub struct RuleSet {
+    path: PathBuf,
+    loaded: RwLock<LoadedRules>,
+}
+
+impl RuleSet {
+    /// Loads `path`, rejecting rules that reuse the id of a detector in
+    /// `reserved`.
+    pub fn load(path: &Path, reserved: &[&str]) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            loaded: RwLock::new(LoadedRules::read(path, reserved)?),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The current rules. Scans hold on to this snapshot, so a reload never
+    /// changes the detectors part way through a file.
+    pub fn detectors(&self) -> Arc<Vec<Box<dyn Detector>>> {
+        self.loaded.read().unwrap().detectors.clone()
+    }
+
+    /// Digest of the rule file the current rules were loaded from.
+    pub fn digest(&self) -> String {
+        self.loaded.read().unwrap().digest.clone()
+    }
+
+    /// Re-reads the file, checking ids against `reserved` as `load` does.
+    /// If it no longer validates the previous rules stay in effect and the
+    /// error is returned.
+    pub fn reload(&self, reserved: &[&str]) -> Result<usize, Box<dyn Error>> {
+        let loaded = LoadedRules::read(&self.path, reserved)?;
+        let count = loaded.detectors.len();
+        *self.loaded.write().unwrap() = loaded;
+        Ok(count)
+    }
+
+    /// Sends on `tx` whenever the rule file is written, created or replaced.
+    /// The parent directory is watched because editors often save by
+    /// renaming a new file over the old one.
+    pub fn watch(&self, tx: mpsc::Sender<()>) -> notify::Result<RecommendedWatcher> {
+        let file_name = self.path.file_name().map(|name| name.to_os_string());
+        let mut watcher = notify::recommended_watcher(move |res: Result<Event, _>| {
+            if let Ok(event) = res {
+                let ours = event.paths.iter().any(|p| p.file_name().map(|n| n.to_os_string()) == file_name);
+                if ours && (event.kind.is_create() || event.kind.is_modify()) {
+                    let _ = tx.try_send(());
+                }
+            }
+        })?;
+
+        let parent = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        watcher.watch(parent, RecursiveMode::NonRecursive)?;
+        Ok(watcher)
+    }
+}
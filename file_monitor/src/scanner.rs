@@ -0,0 +1,625 @@
+use std::path::Path;
+use std::io::{BufReader, Cursor, Read, Seek};
+use std::error::Error;
+use zip::ZipArchive;
+use serde::{Serialize, Deserialize};
+use crate::config::ScannerConfig;
+
+mod archive;
+pub mod document;
+mod encoding;
+pub mod spreadsheet;
+
+use archive::{Budget, Compression};
+use document::DocumentKind;
+use encoding::TextDecoder;
+use spreadsheet::{DelimitedRows, SpreadsheetKind};
+
+/// Where inside a file a piece of extracted text came from.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct ContentOrigin {
+    pub page: Option<u32>,
+    pub sheet: Option<String>,
+    pub cell: Option<String>,
+    /// Path of the archive entry or document part, with nested archives
+    /// joined by `/`, e.g. `outer.zip/report.docx/word/document.xml`.
+    pub entry: Option<String>,
+}
+
+impl ContentOrigin {
+    fn nested_in(mut self, parent: &str) -> Self {
+        self.entry = Some(match self.entry {
+            Some(entry) => format!("{}/{}", parent, entry),
+            None => parent.to_string(),
+        });
+        self
+    }
+}
+
+/// Bytes, newlines and characters since the last newline that precede a
+/// piece of text within its file, after decoding to UTF-8.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextPosition {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl TextPosition {
+    fn advance(mut self, text: &str) -> Self {
+        self.offset += text.len();
+        match text.rfind('\n') {
+            Some(i) => {
+                self.line += text.matches('\n').count();
+                self.column = text[i + 1..].chars().count();
+            }
+            None => self.column += text.chars().count(),
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScannedContent {
+    pub origin: ContentOrigin,
+    pub text: String,
+    /// Where `text` starts; only non-zero for chunks of a streamed file.
+    pub start: TextPosition,
+    /// Matches starting at or after this byte are reported by the next
+    /// chunk, which repeats the tail of this one, or belong to the header
+    /// row that follows a spreadsheet row.
+    pub owned_len: usize,
+    /// Where each cell of a spreadsheet row starts in `text`, with its A1
+    /// reference; empty for other content.
+    pub cells: Vec<(usize, String)>,
+}
+
+impl ScannedContent {
+    pub fn new(origin: ContentOrigin, text: String) -> Self {
+        let owned_len = text.len();
+        Self {
+            origin,
+            text,
+            start: TextPosition::default(),
+            owned_len,
+            cells: Vec::new(),
+        }
+    }
+
+    /// Whether a match starting at byte `start` of `text` belongs to this chunk.
+    pub fn owns(&self, start: usize) -> bool {
+        start < self.owned_len
+    }
+
+    /// The origin of the text at byte `start`, down to the cell for a
+    /// spreadsheet row.
+    pub fn origin_at(&self, start: usize) -> ContentOrigin {
+        let mut origin = self.origin.clone();
+        if let Some((_, cell)) = self.cells.iter().take_while(|(from, _)| *from <= start).last() {
+            origin.cell = Some(cell.clone());
+        }
+        origin
+    }
+}
+
+/// An archive limit that tripped while expanding a file.
+#[derive(Debug, Clone)]
+pub struct LimitViolation {
+    pub origin: ContentOrigin,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ScanOutput {
+    pub contents: Vec<ScannedContent>,
+    pub violations: Vec<LimitViolation>,
+}
+
+impl ScanOutput {
+    fn from_contents(contents: Vec<ScannedContent>) -> Self {
+        Self {
+            contents,
+            violations: Vec::new(),
+        }
+    }
+
+    fn violation(&mut self, origin: ContentOrigin, reason: String) {
+        self.violations.push(LimitViolation { origin, reason });
+    }
+
+    fn nest(&mut self, parent: &str, child: ScanOutput) {
+        for content in child.contents {
+            self.contents.push(ScannedContent {
+                origin: content.origin.nested_in(parent),
+                ..content
+            });
+        }
+        for violation in child.violations {
+            self.violation(violation.origin.nested_in(parent), violation.reason);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Pdf,
+    Spreadsheet(SpreadsheetKind),
+    Zip,
+    /// A tar archive or a single file, either of which may be compressed.
+    Stream(Compression),
+    SevenZip,
+    Text,
+}
+
+impl FileKind {
+    fn classify(mime: Option<&str>, path: &Path) -> Self {
+        // Delimited text has no magic bytes, so fall back to the extension
+        let sheet_kind = match mime {
+            Some(mime) => SpreadsheetKind::from_mime(mime),
+            None => SpreadsheetKind::from_extension(path),
+        };
+        if let Some(sheet_kind) = sheet_kind {
+            return Self::Spreadsheet(sheet_kind);
+        }
+
+        match mime {
+            Some("application/pdf") => Self::Pdf,
+            Some("application/zip") => Self::Zip,
+            Some(mime) if DocumentKind::from_mime(mime).is_some() => Self::Zip,
+            Some("application/x-tar") => Self::Stream(Compression::None),
+            Some("application/x-7z-compressed") => Self::SevenZip,
+            Some(mime) => Compression::from_mime(mime).map(Self::Stream).unwrap_or(Self::Text),
+            None => Self::Text,
+        }
+    }
+
+    fn is_archive(&self) -> bool {
+        matches!(self, Self::Zip | Self::Stream(_) | Self::SevenZip)
+    }
+}
+
+pub struct ContentScanner {
+    infer: infer::Infer,
+    config: ScannerConfig,
+}
+
+impl Default for ContentScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContentScanner {
+    pub fn new() -> Self {
+        Self::with_config(ScannerConfig::default())
+    }
+
+    pub fn with_config(config: ScannerConfig) -> Self {
+        Self {
+            infer: infer::Infer::new(),
+            config,
+        }
+    }
+
+    pub async fn scan(&self, path: &Path) -> Result<ScanOutput, Box<dyn Error>> {
+        let mut contents = Vec::new();
+        let violations = self
+            .scan_each(path, self.config.text.max_overlap_bytes, |content| contents.push(content))
+            .await?;
+        Ok(ScanOutput { contents, violations })
+    }
+
+    /// Like `scan`, but hands every piece of content to `each` as soon as it
+    /// is extracted. Plain text is streamed in chunks that repeat the last
+    /// `overlap` bytes of the previous chunk, so memory stays bounded however
+    /// large the file is.
+    pub async fn scan_each<F: FnMut(ScannedContent)>(
+        &self,
+        path: &Path,
+        overlap: usize,
+        mut each: F,
+    ) -> Result<Vec<LimitViolation>, Box<dyn Error>> {
+        let kind = self.infer.get_from_path(path)?;
+
+        let output = match FileKind::classify(kind.map(|k| k.mime_type()), path) {
+            FileKind::Pdf => self.scan_pdf(path).await.map(ScanOutput::from_contents)?,
+            FileKind::Spreadsheet(kind) if kind.is_delimited() => {
+                self.scan_delimited(path, kind, &mut each).await?;
+                return Ok(Vec::new());
+            }
+            FileKind::Spreadsheet(kind) => self.scan_spreadsheet(path, kind).await?,
+            FileKind::Zip => self.scan_zip(path).await?,
+            FileKind::Stream(compression) => self.scan_stream(path, compression).await?,
+            FileKind::SevenZip => self.scan_7z(path).await?,
+            FileKind::Text => {
+                self.scan_text(path, overlap, &mut each).await?;
+                return Ok(Vec::new());
+            }
+        };
+
+        output.contents.into_iter().for_each(each);
+        Ok(output.violations)
+    }
+
+    /// Runs `job` on tokio's blocking thread pool. Archive expansion and
+    /// document parsing read or decode a whole file in one call, so they go
+    /// there rather than hold up the async workers; once started they cannot
+    /// be cancelled, and run to completion even if the scan is abandoned.
+    async fn blocking<T, F>(&self, path: &Path, job: F) -> Result<T, Box<dyn Error>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&ContentScanner, &Path) -> Result<T, Box<dyn Error>> + Send + 'static,
+    {
+        let scanner = ContentScanner::with_config(self.config.clone());
+        let path = path.to_path_buf();
+        let result = tokio::task::spawn_blocking(move || job(&scanner, &path).map_err(|e| e.to_string())).await?;
+        Ok(result?)
+    }
+
+    async fn scan_zip(&self, path: &Path) -> Result<ScanOutput, Box<dyn Error>> {
+        self.blocking(path, |scanner, path| {
+            let archive = ZipArchive::new(BufReader::new(std::fs::File::open(path)?))?;
+            let mut budget = Budget::new(&scanner.config.archive);
+            let mut output = ScanOutput::default();
+
+            scanner.expand_zip(archive, 1, &mut budget, &mut output)?;
+            Ok(output)
+        })
+        .await
+    }
+
+    async fn scan_stream(&self, path: &Path, compression: Compression) -> Result<ScanOutput, Box<dyn Error>> {
+        self.blocking(path, move |scanner, path| {
+            let file = std::fs::File::open(path)?;
+            let compressed_size = file.metadata()?.len();
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            let mut budget = Budget::new(&scanner.config.archive);
+            let mut output = ScanOutput::default();
+
+            scanner.expand_stream(BufReader::new(file), compression, &name, compressed_size, 1, &mut budget, &mut output)?;
+            Ok(output)
+        })
+        .await
+    }
+
+    async fn scan_7z(&self, path: &Path) -> Result<ScanOutput, Box<dyn Error>> {
+        self.blocking(path, |scanner, path| {
+            let file = std::fs::File::open(path)?;
+            let len = file.metadata()?.len();
+            let mut budget = Budget::new(&scanner.config.archive);
+            let mut output = ScanOutput::default();
+
+            scanner.expand_7z(BufReader::new(file), len, 1, &mut budget, &mut output)?;
+            Ok(output)
+        })
+        .await
+    }
+
+    /// Expands one zip archive `depth` levels below the scanned file,
+    /// dispatching every entry through the same format pipeline as a file
+    /// on disk and charging everything read against `budget`.
+    fn expand_zip<R: Read + Seek>(
+        &self,
+        mut archive: ZipArchive<R>,
+        depth: usize,
+        budget: &mut Budget,
+        output: &mut ScanOutput,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(kind) = DocumentKind::sniff(&mut archive) {
+            return document::extract(&mut archive, kind, budget, output);
+        }
+        if let Some(kind) = SpreadsheetKind::sniff(&mut archive) {
+            return self.extract_spreadsheet(archive.into_inner(), kind, 0, budget, output);
+        }
+
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let name = entry.name().to_string();
+            let origin = ContentOrigin {
+                entry: Some(name.clone()),
+                ..Default::default()
+            };
+
+            // Skip entries whose declared sizes already give them away
+            if let Err(reason) = budget.check_ratio(&name, entry.size(), entry.compressed_size()) {
+                output.violation(origin, reason);
+                continue;
+            }
+
+            let compressed_size = entry.compressed_size();
+            if !self.expand_entry(&name, &mut entry, Some(compressed_size), depth, budget, output)? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Expands a tar archive, or the single file inside a bare gzip, bzip2,
+    /// xz or zstd stream, reading entries in order without seeking.
+    #[allow(clippy::too_many_arguments)]
+    fn expand_stream<R: Read>(
+        &self,
+        reader: R,
+        compression: Compression,
+        name: &str,
+        compressed_size: u64,
+        depth: usize,
+        budget: &mut Budget,
+        output: &mut ScanOutput,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut decoder = compression.decoder(reader)?;
+
+        // Only the decompressed header tells a tarball from a compressed file
+        let mut header = Vec::new();
+        (&mut decoder).take(512).read_to_end(&mut header)?;
+        let is_tar = infer::archive::is_tar(&header);
+        let mut stream = Cursor::new(header).chain(decoder);
+
+        if is_tar {
+            let mut archive = tar::Archive::new(stream);
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let entry_name = entry.path()?.to_string_lossy().into_owned();
+                if !self.expand_entry(&entry_name, &mut entry, None, depth, budget, output)? {
+                    break;
+                }
+            }
+            return Ok(());
+        }
+
+        let inner_name = compression.inner_name(name);
+        self.expand_entry(&inner_name, &mut stream, Some(compressed_size), depth, budget, output)?;
+        Ok(())
+    }
+
+    /// Expands a 7z archive. Solid archives compress many entries as one
+    /// block, so only the expanded size and entry count are enforced here.
+    fn expand_7z<R: Read + Seek>(
+        &self,
+        reader: R,
+        len: u64,
+        depth: usize,
+        budget: &mut Budget,
+        output: &mut ScanOutput,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut archive = sevenz_rust::SevenZReader::new(reader, len, sevenz_rust::Password::empty())?;
+        let mut result = Ok(());
+
+        archive.for_each_entries(|entry, reader| {
+            if entry.is_directory() {
+                return Ok(true);
+            }
+            match self.expand_entry(entry.name(), reader, None, depth, budget, output) {
+                Ok(more) => Ok(more),
+                Err(e) => {
+                    result = Err(e);
+                    Ok(false)
+                }
+            }
+        })?;
+
+        result
+    }
+
+    /// Reads one archive entry within `budget` and scans it, nesting what it
+    /// finds under `name`. Returns `false` once a limit has tripped and the
+    /// rest of the archive should be skipped.
+    fn expand_entry(
+        &self,
+        name: &str,
+        reader: &mut dyn Read,
+        compressed_size: Option<u64>,
+        depth: usize,
+        budget: &mut Budget,
+        output: &mut ScanOutput,
+    ) -> Result<bool, Box<dyn Error>> {
+        let origin = ContentOrigin {
+            entry: Some(name.to_string()),
+            ..Default::default()
+        };
+        if let Err(reason) = budget.take_entry() {
+            output.violation(origin, reason);
+            return Ok(false);
+        }
+
+        // Never trust the declared size: read at most one byte past the budget
+        let mut data = Vec::new();
+        reader.take(budget.remaining_bytes() + 1).read_to_end(&mut data)?;
+        let checked = budget.take_bytes(data.len() as u64).and_then(|_| match compressed_size {
+            Some(compressed_size) => budget.check_ratio(name, data.len() as u64, compressed_size),
+            None => Ok(()),
+        });
+        if let Err(reason) = checked {
+            output.violation(origin, reason);
+            return Ok(false);
+        }
+
+        let mut nested = ScanOutput::default();
+        match self.scan_entry(name, data, depth, budget, &mut nested) {
+            Ok(()) => output.nest(name, nested),
+            Err(e) => eprintln!("Error scanning archive entry {}: {}", name, e),
+        }
+        Ok(true)
+    }
+
+    /// Dispatches an in-memory archive entry by its content and name.
+    fn scan_entry(
+        &self,
+        name: &str,
+        data: Vec<u8>,
+        depth: usize,
+        budget: &mut Budget,
+        output: &mut ScanOutput,
+    ) -> Result<(), Box<dyn Error>> {
+        let mime = self.infer.get(&data).map(|k| k.mime_type());
+
+        match FileKind::classify(mime, Path::new(name)) {
+            FileKind::Pdf => output.contents.extend(pdf_text(lopdf::Document::load_mem(&data)?)),
+            // Already charged as an entry, but zipped workbooks expand further
+            FileKind::Spreadsheet(kind) => self.extract_spreadsheet(Cursor::new(data), kind, 0, budget, output)?,
+            kind if kind.is_archive() && depth >= budget.max_depth() => {
+                output.violation(ContentOrigin::default(), format!("archives nested more than {} deep", budget.max_depth()));
+            }
+            FileKind::Zip => self.expand_zip(ZipArchive::new(Cursor::new(data))?, depth + 1, budget, output)?,
+            FileKind::Stream(compression) => {
+                let compressed_size = data.len() as u64;
+                self.expand_stream(Cursor::new(data), compression, name, compressed_size, depth + 1, budget, output)?
+            }
+            FileKind::SevenZip => {
+                let len = data.len() as u64;
+                self.expand_7z(Cursor::new(data), len, depth + 1, budget, output)?
+            }
+            FileKind::Text => {
+                let mut text = String::new();
+                TextDecoder::sniff(&data).decode(&data, true, &mut text);
+                output.contents.push(ScannedContent::new(ContentOrigin::default(), text));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn scan_spreadsheet(&self, path: &Path, kind: SpreadsheetKind) -> Result<ScanOutput, Box<dyn Error>> {
+        self.blocking(path, move |scanner, path| {
+            let file = std::fs::File::open(path)?;
+            let len = file.metadata()?.len();
+            let mut budget = Budget::new(&scanner.config.archive);
+            let mut output = ScanOutput::default();
+
+            scanner.extract_spreadsheet(BufReader::new(file), kind, len, &mut budget, &mut output)?;
+            Ok(output)
+        })
+        .await
+    }
+
+    /// Extracts a workbook within `budget`. calamine reads zipped workbooks
+    /// without any limit, so they are inflated once up front to charge what
+    /// their parts really expand to. Other kinds are charged `len` bytes,
+    /// their size as stored; CSV and TSV on disk are streamed by
+    /// `scan_delimited` instead.
+    fn extract_spreadsheet<R: Read + Seek>(
+        &self,
+        mut reader: R,
+        kind: SpreadsheetKind,
+        len: u64,
+        budget: &mut Budget,
+        output: &mut ScanOutput,
+    ) -> Result<(), Box<dyn Error>> {
+        let admitted = if kind.is_zipped() {
+            budget.measure(&mut ZipArchive::new(&mut reader)?)
+        } else {
+            budget.take_bytes(len)
+        };
+        if let Err(reason) = admitted {
+            output.violation(ContentOrigin::default(), reason);
+            return Ok(());
+        }
+
+        reader.rewind()?;
+        output.contents.extend(spreadsheet::extract(reader, kind)?);
+        Ok(())
+    }
+
+    async fn scan_pdf(&self, path: &Path) -> Result<Vec<ScannedContent>, Box<dyn Error>> {
+        self.blocking(path, |_, path| Ok(pdf_text(lopdf::Document::load(path)?))).await
+    }
+
+    async fn scan_text<F: FnMut(ScannedContent)>(&self, path: &Path, overlap: usize, each: &mut F) -> Result<(), Box<dyn Error>> {
+        let limits = &self.config.text;
+        let overlap = overlap.min(limits.max_overlap_bytes).min(limits.chunk_bytes.max(1) / 2);
+        let mut carry = String::new();
+        let mut start = TextPosition::default();
+
+        self.read_text(path, |decoded, last| {
+            let mut text = std::mem::take(&mut carry);
+            text.push_str(&decoded);
+            let owned_len = if last { text.len() } else { floor_char_boundary(&text, text.len() - overlap.min(text.len())) };
+            carry = text[owned_len..].to_string();
+            let next = start.advance(&text[..owned_len]);
+            each(ScannedContent {
+                origin: ContentOrigin::default(),
+                text,
+                start,
+                owned_len,
+                cells: Vec::new(),
+            });
+            start = next;
+        })
+        .await
+    }
+
+    /// Streams CSV or TSV a row at a time through the same reader as plain
+    /// text. Rows are never held all at once, so unlike workbooks they are
+    /// not charged to the archive budget.
+    async fn scan_delimited<F: FnMut(ScannedContent)>(&self, path: &Path, kind: SpreadsheetKind, each: &mut F) -> Result<(), Box<dyn Error>> {
+        let mut rows = DelimitedRows::new(kind, self.config.text.chunk_bytes.max(1));
+        self.read_text(path, |text, last| rows.feed(text.as_bytes(), last, &mut *each)).await
+    }
+
+    /// Reads up to `TextLimits::max_bytes_per_file` of `path` in
+    /// `chunk_bytes` pieces and hands each to `each` decoded to UTF-8, with
+    /// whether it is the last.
+    async fn read_text<F: FnMut(String, bool)>(&self, path: &Path, mut each: F) -> Result<(), Box<dyn Error>> {
+        use tokio::io::AsyncReadExt;
+
+        let limits = &self.config.text;
+        let chunk_bytes = limits.chunk_bytes.max(1);
+        let file = tokio::fs::File::open(path).await?;
+        let mut reader = tokio::io::BufReader::new(file).take(limits.max_bytes_per_file);
+
+        let mut decoder = None;
+        loop {
+            let mut bytes = Vec::new();
+            let read = (&mut reader).take(chunk_bytes as u64).read_to_end(&mut bytes).await?;
+            let last = read < chunk_bytes;
+
+            // The encoding is chosen once, from the first chunk
+            let decoder = decoder.get_or_insert_with(|| TextDecoder::sniff(&bytes));
+            let mut text = String::new();
+            decoder.decode(&bytes, last, &mut text);
+            each(text, last);
+
+            if last {
+                break;
+            }
+            // Let other scans and the event loop run, and a timeout fire,
+            // between chunks of a large file
+            tokio::task::yield_now().await;
+        }
+
+        if reader.limit() == 0 && reader.into_inner().read(&mut [0u8; 1]).await? > 0 {
+            eprintln!("Scanned only the first {} bytes of {:?}", limits.max_bytes_per_file, path);
+        }
+        Ok(())
+    }
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn pdf_text(doc: lopdf::Document) -> Vec<ScannedContent> {
+    let mut text = Vec::new();
+
+    for page_num in doc.get_pages().keys() {
+        if let Ok(content) = doc.extract_text(&[*page_num]) {
+            let origin = ContentOrigin {
+                page: Some(*page_num),
+                ..Default::default()
+            };
+            text.push(ScannedContent::new(origin, content));
+        }
+    }
+
+    text
+}
@@ -0,0 +1,146 @@
+use std::io::{Read, Seek};
+use zip::read::ZipFile;
+use zip::ZipArchive;
+use crate::config::ArchiveLimits;
+
+/// Small entries compress extremely well without being dangerous, so the
+/// ratio check only applies above this size.
+const RATIO_MIN_BYTES: u64 = 1024 * 1024;
+
+/// Running totals for one top-level file, shared by every nested archive.
+pub(crate) struct Budget<'a> {
+    limits: &'a ArchiveLimits,
+    entries: usize,
+    expanded_bytes: u64,
+}
+
+impl<'a> Budget<'a> {
+    pub(crate) fn new(limits: &'a ArchiveLimits) -> Self {
+        Self {
+            limits,
+            entries: 0,
+            expanded_bytes: 0,
+        }
+    }
+
+    pub(crate) fn max_depth(&self) -> usize {
+        self.limits.max_depth
+    }
+
+    pub(crate) fn remaining_bytes(&self) -> u64 {
+        self.limits.max_expanded_bytes.saturating_sub(self.expanded_bytes)
+    }
+
+    pub(crate) fn take_entry(&mut self) -> Result<(), String> {
+        self.entries += 1;
+        if self.entries > self.limits.max_entries {
+            return Err(format!("more than {} archive entries", self.limits.max_entries));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn take_bytes(&mut self, bytes: u64) -> Result<(), String> {
+        self.expanded_bytes += bytes;
+        if self.expanded_bytes > self.limits.max_expanded_bytes {
+            return Err(format!("more than {} expanded bytes", self.limits.max_expanded_bytes));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_ratio(&self, name: &str, size: u64, compressed_size: u64) -> Result<(), String> {
+        if size < RATIO_MIN_BYTES {
+            return Ok(());
+        }
+        let ratio = size as f64 / compressed_size.max(1) as f64;
+        if ratio > self.limits.max_compression_ratio {
+            return Err(format!("entry {} has compression ratio {:.0}:1", name, ratio));
+        }
+        Ok(())
+    }
+
+    /// Reads a zip entry, at most one byte past the budget, and charges what
+    /// it actually inflates to: the sizes the central directory declares are
+    /// only checked against the CRC once an entry is read to the end.
+    pub(crate) fn read_entry(&mut self, entry: &mut ZipFile<'_>) -> Result<Vec<u8>, String> {
+        let name = entry.name().to_string();
+        self.take_entry()?;
+        let mut data = Vec::new();
+        entry.take(self.remaining_bytes() + 1).read_to_end(&mut data).map_err(|e| format!("entry {}: {}", name, e))?;
+        self.take_bytes(data.len() as u64)?;
+        self.check_ratio(&name, data.len() as u64, entry.compressed_size())?;
+        Ok(data)
+    }
+
+    /// Inflates every entry without keeping it, charging the bytes each one
+    /// really expands to. Used for workbooks, whose parts calamine reads
+    /// itself without any limit.
+    pub(crate) fn measure<R: Read + Seek>(&mut self, archive: &mut ZipArchive<R>) -> Result<(), String> {
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+            if entry.is_dir() {
+                continue;
+            }
+            let name = entry.name().to_string();
+            self.take_entry()?;
+            let size = std::io::copy(&mut (&mut entry).take(self.remaining_bytes() + 1), &mut std::io::sink())
+                .map_err(|e| format!("entry {}: {}", name, e))?;
+            self.take_bytes(size)?;
+            self.check_ratio(&name, size, entry.compressed_size())?;
+        }
+        Ok(())
+    }
+}
+
+/// Stream compressions that may wrap a tar archive or a single file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    pub(crate) fn from_mime(mime: &str) -> Option<Self> {
+        match mime {
+            "application/gzip" => Some(Self::Gzip),
+            "application/x-bzip2" => Some(Self::Bzip2),
+            "application/x-xz" => Some(Self::Xz),
+            "application/zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn decoder<'r, R: Read + 'r>(&self, reader: R) -> std::io::Result<Box<dyn Read + 'r>> {
+        Ok(match self {
+            Self::None => Box::new(reader),
+            Self::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Self::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+            Self::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+            Self::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        })
+    }
+
+    /// Name of the decompressed payload, e.g. `notes.txt.gz` to `notes.txt`
+    /// and `backup.tgz` to `backup.tar`.
+    pub(crate) fn inner_name(&self, name: &str) -> String {
+        let lower = name.to_ascii_lowercase();
+        for (suffix, replacement) in [
+            (".tgz", ".tar"),
+            (".tbz2", ".tar"),
+            (".tbz", ".tar"),
+            (".txz", ".tar"),
+            (".tzst", ".tar"),
+            (".gz", ""),
+            (".bz2", ""),
+            (".xz", ""),
+            (".zst", ""),
+        ] {
+            if lower.ends_with(suffix) {
+                return format!("{}{}", &name[..name.len() - suffix.len()], replacement);
+            }
+        }
+        name.to_string()
+    }
+}
@@ -0,0 +1,161 @@
+use std::error::Error;
+use std::io::{Read, Seek};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use zip::ZipArchive;
+use super::archive::Budget;
+use super::{ContentOrigin, ScanOutput, ScannedContent};
+
+/// Office Open XML and OpenDocument formats we extract text from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Docx,
+    Pptx,
+    Odt,
+    Odp,
+}
+
+impl DocumentKind {
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        match mime {
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => Some(Self::Docx),
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation" => Some(Self::Pptx),
+            "application/vnd.oasis.opendocument.text" => Some(Self::Odt),
+            "application/vnd.oasis.opendocument.presentation" => Some(Self::Odp),
+            _ => None,
+        }
+    }
+
+    /// `infer` only recognises OOXML when the parts are stored in the order
+    /// Office writes them, so archives reported as plain zip are checked by
+    /// their contents as well.
+    pub fn sniff<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Option<Self> {
+        if archive.by_name("word/document.xml").is_ok() {
+            return Some(Self::Docx);
+        }
+        if archive.by_name("ppt/presentation.xml").is_ok() {
+            return Some(Self::Pptx);
+        }
+
+        let mut mimetype = String::new();
+        archive.by_name("mimetype").ok()?.read_to_string(&mut mimetype).ok()?;
+        Self::from_mime(mimetype.trim())
+    }
+
+    fn is_text_part(&self, name: &str) -> bool {
+        match self {
+            Self::Docx => {
+                name.ends_with(".xml")
+                    && ["word/document", "word/header", "word/footer", "word/comments", "word/footnotes", "word/endnotes"]
+                        .iter()
+                        .any(|prefix| name.starts_with(prefix))
+            }
+            Self::Pptx => {
+                name.ends_with(".xml")
+                    && ["ppt/slides/slide", "ppt/notesSlides/notesSlide", "ppt/comments/"]
+                        .iter()
+                        .any(|prefix| name.starts_with(prefix))
+            }
+            // Headers and footers live in the master pages inside styles.xml
+            Self::Odt | Self::Odp => name == "content.xml" || name == "styles.xml",
+        }
+    }
+
+    fn is_open_document(&self) -> bool {
+        matches!(self, Self::Odt | Self::Odp)
+    }
+}
+
+/// Extracts the text of every content part (body, headers and footers,
+/// comments, speaker notes, inserted and deleted tracked changes), one
+/// `ScannedContent` per part. Parts are read within `budget`; once it is
+/// spent the rest of the document is skipped with a violation.
+pub(crate) fn extract<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    kind: DocumentKind,
+    budget: &mut Budget,
+    output: &mut ScanOutput,
+) -> Result<(), Box<dyn Error>> {
+    let mut names: Vec<String> = archive
+        .file_names()
+        .filter(|name| kind.is_text_part(name))
+        .map(String::from)
+        .collect();
+    names.sort_by_key(|name| {
+        let stem = name.trim_end_matches(".xml").trim_end_matches(|c: char| c.is_ascii_digit()).to_string();
+        (stem, part_number(name))
+    });
+
+    for name in names {
+        let xml = match budget.read_entry(&mut archive.by_name(&name)?) {
+            Ok(xml) => xml,
+            Err(reason) => {
+                let origin = ContentOrigin {
+                    entry: Some(name),
+                    ..Default::default()
+                };
+                output.violation(origin, reason);
+                break;
+            }
+        };
+
+        let content = xml_text(&xml, kind.is_open_document())?;
+        if content.trim().is_empty() {
+            continue;
+        }
+
+        let origin = ContentOrigin {
+            page: if name.starts_with("ppt/slides/") { part_number(&name) } else { None },
+            entry: Some(name),
+            ..Default::default()
+        };
+        output.contents.push(ScannedContent::new(origin, content));
+    }
+
+    Ok(())
+}
+
+/// The trailing number of parts such as `ppt/slides/slide12.xml`.
+fn part_number(name: &str) -> Option<u32> {
+    let stem = name.rsplit('/').next()?.strip_suffix(".xml")?;
+    let digits: String = stem.chars().rev().take_while(|c| c.is_ascii_digit()).collect();
+    digits.chars().rev().collect::<String>().parse().ok()
+}
+
+/// In OOXML only `w:t`/`a:t`-style runs hold document text (`delText` for
+/// tracked deletions, `text` for legacy PowerPoint comments); everything else
+/// is markup. In OpenDocument all character data is content.
+fn xml_text(xml: &[u8], open_document: bool) -> Result<String, Box<dyn Error>> {
+    let mut reader = Reader::from_reader(xml);
+    let mut buf = Vec::new();
+    let mut text = String::new();
+    let mut in_run = 0usize;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => {
+                if matches!(e.local_name().as_ref(), b"t" | b"delText" | b"text") {
+                    in_run += 1;
+                }
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"t" | b"delText" | b"text" => in_run = in_run.saturating_sub(1),
+                b"p" | b"h" => text.push('\n'),
+                _ => {}
+            },
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"tab" => text.push('\t'),
+                b"br" | b"line-break" => text.push('\n'),
+                b"s" if open_document => text.push(' '),
+                _ => {}
+            },
+            Event::Text(e) if open_document || in_run > 0 => text.push_str(&e.unescape()?),
+            Event::CData(e) if open_document || in_run > 0 => text.push_str(&String::from_utf8_lossy(&e)),
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(text)
+}
@@ -0,0 +1,122 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Decoder, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Shortest run of printable bytes kept when falling back to string
+/// extraction, as with `strings(1)`.
+const MIN_STRING_LEN: usize = 4;
+
+/// Share of control bytes above which a sample is treated as binary.
+const BINARY_CONTROL_RATIO: f64 = 0.1;
+
+/// Turns the raw bytes of a text file into UTF-8, one chunk at a time.
+pub(crate) enum TextDecoder {
+    Charset(Decoder),
+    /// Nothing decodes cleanly, so only printable ASCII runs are kept.
+    Strings(PrintableStrings),
+}
+
+impl TextDecoder {
+    /// Picks a decoder from the first bytes of a file: a byte order mark if
+    /// there is one, then UTF-16 without a BOM, valid UTF-8, binary content,
+    /// and finally a statistical guess among legacy encodings.
+    pub(crate) fn sniff(sample: &[u8]) -> Self {
+        if let Some((encoding, _)) = Encoding::for_bom(sample) {
+            return Self::Charset(encoding.new_decoder_with_bom_removal());
+        }
+        if let Some(encoding) = utf16_without_bom(sample) {
+            return Self::Charset(encoding.new_decoder_without_bom_handling());
+        }
+        if is_utf8(sample) {
+            return Self::Charset(UTF_8.new_decoder_without_bom_handling());
+        }
+        if looks_binary(sample) {
+            return Self::Strings(PrintableStrings::default());
+        }
+
+        let mut detector = EncodingDetector::new();
+        detector.feed(sample, false);
+        Self::Charset(detector.guess(None, false).new_decoder_without_bom_handling())
+    }
+
+    /// Appends the text decoded from `bytes` to `out`. Characters split
+    /// across calls are held back until the next one.
+    pub(crate) fn decode(&mut self, bytes: &[u8], last: bool, out: &mut String) {
+        match self {
+            Self::Charset(decoder) => {
+                if let Some(len) = decoder.max_utf8_buffer_length(bytes.len()) {
+                    out.reserve(len);
+                }
+                // Malformed sequences become U+FFFD rather than failing the file
+                let _ = decoder.decode_to_string(bytes, out, last);
+            }
+            Self::Strings(strings) => strings.decode(bytes, last, out),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct PrintableStrings {
+    run: String,
+}
+
+impl PrintableStrings {
+    fn decode(&mut self, bytes: &[u8], last: bool, out: &mut String) {
+        for &byte in bytes {
+            if byte == b'\t' || (0x20..0x7f).contains(&byte) {
+                self.run.push(byte as char);
+            } else {
+                self.flush(out);
+            }
+        }
+        if last {
+            self.flush(out);
+        }
+    }
+
+    fn flush(&mut self, out: &mut String) {
+        if self.run.len() >= MIN_STRING_LEN {
+            out.push_str(&self.run);
+            out.push('\n');
+        }
+        self.run.clear();
+    }
+}
+
+/// ASCII-heavy UTF-16 has a zero in every other byte.
+fn utf16_without_bom(sample: &[u8]) -> Option<&'static Encoding> {
+    let pairs = sample.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let even_zeros = sample.iter().step_by(2).take(pairs).filter(|&&b| b == 0).count();
+    let odd_zeros = sample.iter().skip(1).step_by(2).take(pairs).filter(|&&b| b == 0).count();
+
+    let mostly = |zeros: usize| zeros as f64 / pairs as f64 > 0.4;
+    let rarely = |zeros: usize| (zeros as f64 / pairs as f64) < 0.05;
+    if mostly(odd_zeros) && rarely(even_zeros) {
+        Some(UTF_16LE)
+    } else if mostly(even_zeros) && rarely(odd_zeros) {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Valid UTF-8, allowing the sample to end part way through a character.
+fn is_utf8(sample: &[u8]) -> bool {
+    match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+fn looks_binary(sample: &[u8]) -> bool {
+    if sample.is_empty() {
+        return false;
+    }
+    let control = sample
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+        .count();
+    control as f64 / sample.len() as f64 > BINARY_CONTROL_RATIO
+}
@@ -0,0 +1,293 @@
+use std::error::Error;
+use std::io::{Read, Seek};
+use std::path::Path;
+use calamine::{open_workbook_from_rs, DataType, Ods, Range, Reader, Sheets, Xls, Xlsb, Xlsx};
+use csv_core::ReadRecordResult;
+use zip::ZipArchive;
+use super::{ContentOrigin, ScannedContent, TextPosition};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpreadsheetKind {
+    Xls,
+    Xlsx,
+    Xlsb,
+    Ods,
+    Csv,
+    Tsv,
+}
+
+impl SpreadsheetKind {
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        match mime {
+            "application/vnd.ms-excel" => Some(Self::Xls),
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => Some(Self::Xlsx),
+            "application/vnd.oasis.opendocument.spreadsheet" => Some(Self::Ods),
+            "text/csv" => Some(Self::Csv),
+            "text/tab-separated-values" => Some(Self::Tsv),
+            _ => None,
+        }
+    }
+
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "xls" | "xla" => Some(Self::Xls),
+            "xlsx" | "xlsm" | "xltx" | "xltm" | "xlam" => Some(Self::Xlsx),
+            "xlsb" => Some(Self::Xlsb),
+            "ods" => Some(Self::Ods),
+            "csv" => Some(Self::Csv),
+            "tsv" | "tab" => Some(Self::Tsv),
+            _ => None,
+        }
+    }
+
+    /// Workbooks that `infer` reported as plain zip. `.xlsb` is never
+    /// recognised by mime type, only by its binary workbook part.
+    pub fn sniff<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Option<Self> {
+        if archive.by_name("xl/workbook.xml").is_ok() {
+            return Some(Self::Xlsx);
+        }
+        if archive.by_name("xl/workbook.bin").is_ok() {
+            return Some(Self::Xlsb);
+        }
+
+        let mut mimetype = String::new();
+        archive.by_name("mimetype").ok()?.read_to_string(&mut mimetype).ok()?;
+        match mimetype.trim() {
+            "application/vnd.oasis.opendocument.spreadsheet" => Some(Self::Ods),
+            _ => None,
+        }
+    }
+
+    /// Workbooks stored as zip archives.
+    pub fn is_zipped(&self) -> bool {
+        matches!(self, Self::Xlsx | Self::Xlsb | Self::Ods)
+    }
+
+    pub fn is_delimited(&self) -> bool {
+        matches!(self, Self::Csv | Self::Tsv)
+    }
+
+    fn separator(&self) -> char {
+        match self {
+            Self::Csv => ',',
+            _ => '\t',
+        }
+    }
+}
+
+/// One `ScannedContent` per non-empty row, tagged with its sheet and with
+/// the A1 reference of each cell.
+pub fn extract<RS: Read + Seek>(reader: RS, kind: SpreadsheetKind) -> Result<Vec<ScannedContent>, Box<dyn Error>> {
+    if kind.is_delimited() {
+        extract_delimited(reader, kind)
+    } else {
+        extract_workbook(reader, kind)
+    }
+}
+
+fn extract_workbook<RS: Read + Seek>(reader: RS, kind: SpreadsheetKind) -> Result<Vec<ScannedContent>, Box<dyn Error>> {
+    let mut workbook = match kind {
+        SpreadsheetKind::Xls => Sheets::Xls(open_workbook_from_rs::<Xls<_>, _>(reader)?),
+        SpreadsheetKind::Xlsb => Sheets::Xlsb(open_workbook_from_rs::<Xlsb<_>, _>(reader)?),
+        SpreadsheetKind::Ods => Sheets::Ods(open_workbook_from_rs::<Ods<_>, _>(reader)?),
+        _ => Sheets::Xlsx(open_workbook_from_rs::<Xlsx<_>, _>(reader)?),
+    };
+    let mut text = Vec::new();
+
+    let sheet_names = workbook.sheet_names().to_owned();
+    for name in sheet_names {
+        if let Some(Ok(range)) = workbook.worksheet_range(&name) {
+            text.extend(range_rows(&range, &name));
+        }
+    }
+
+    Ok(text)
+}
+
+fn range_rows(range: &Range<DataType>, sheet: &str) -> Vec<ScannedContent> {
+    let (start_row, start_col) = range.start().unwrap_or((0, 0));
+    let origin = ContentOrigin {
+        sheet: Some(sheet.to_string()),
+        ..Default::default()
+    };
+    let mut header: Option<String> = None;
+    let mut text = Vec::new();
+
+    for (row, cells) in range.rows().enumerate() {
+        let values = cells.iter().enumerate().map(|(col, cell)| (start_col + col as u32, cell.to_string()));
+        if let Some(content) = row_content(origin.clone(), start_row + row as u32, values, '\t', header.as_deref()) {
+            header.get_or_insert_with(|| content.text[..content.owned_len].to_string());
+            text.push(content);
+        }
+    }
+
+    text
+}
+
+fn extract_delimited<R: Read>(mut reader: R, kind: SpreadsheetKind) -> Result<Vec<ScannedContent>, Box<dyn Error>> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let mut text = Vec::new();
+    DelimitedRows::new(kind, usize::MAX).feed(&data, true, &mut |content| text.push(content));
+    Ok(text)
+}
+
+/// Joins the non-empty cells of one row, each `(column, value)`, into one
+/// piece of text, so keyword rules see the neighbouring cells. The header
+/// row follows it for the same reason, outside the owned text so it is not
+/// reported again for every row.
+fn row_content<I>(origin: ContentOrigin, row: u32, values: I, separator: char, header: Option<&str>) -> Option<ScannedContent>
+where
+    I: IntoIterator<Item = (u32, String)>,
+{
+    let mut text = String::new();
+    let mut cells = Vec::new();
+    for (col, value) in values {
+        if value.is_empty() {
+            continue;
+        }
+        if !text.is_empty() {
+            text.push(separator);
+        }
+        cells.push((text.len(), cell_reference(row, col)));
+        text.push_str(&value);
+    }
+    if text.is_empty() {
+        return None;
+    }
+
+    let owned_len = text.len();
+    if let Some(header) = header {
+        text.push('\n');
+        text.push_str(header);
+    }
+    Some(ScannedContent {
+        origin,
+        text,
+        start: TextPosition::default(),
+        owned_len,
+        cells,
+    })
+}
+
+/// Splits CSV or TSV, fed a piece at a time, into one `ScannedContent` per
+/// row. A row longer than `max_row_bytes` is handed over in pieces.
+pub(crate) struct DelimitedRows {
+    reader: csv_core::Reader,
+    separator: char,
+    max_row_bytes: usize,
+    /// Unescaped bytes of the row being read, and where each field ends
+    /// counting from the start of the row.
+    fields: Vec<u8>,
+    ends: Vec<usize>,
+    used: usize,
+    ended: usize,
+    /// Bytes and fields of the row already handed over.
+    flushed: usize,
+    column: u32,
+    row: u32,
+    consumed: usize,
+    start: TextPosition,
+    header: Option<String>,
+}
+
+impl DelimitedRows {
+    pub(crate) fn new(kind: SpreadsheetKind, max_row_bytes: usize) -> Self {
+        let separator = kind.separator();
+        Self {
+            reader: csv_core::ReaderBuilder::new().delimiter(separator as u8).build(),
+            separator,
+            max_row_bytes: max_row_bytes.max(1),
+            fields: vec![0; max_row_bytes.clamp(1, 1024)],
+            ends: vec![0; 64],
+            used: 0,
+            ended: 0,
+            flushed: 0,
+            column: 0,
+            row: 0,
+            consumed: 0,
+            start: TextPosition::default(),
+            header: None,
+        }
+    }
+
+    /// Parses `input` and hands `each` every row it completes. The row in
+    /// progress waits for the next call, until `last`.
+    pub(crate) fn feed<F: FnMut(ScannedContent)>(&mut self, mut input: &[u8], last: bool, each: &mut F) {
+        loop {
+            let (result, read, written, ended) =
+                self.reader.read_record(input, &mut self.fields[self.used..], &mut self.ends[self.ended..]);
+            input = &input[read..];
+            self.consumed += read;
+            self.used += written;
+            self.ended += ended;
+
+            match result {
+                // An empty input tells the reader the data has ended
+                ReadRecordResult::InputEmpty if !last => return,
+                ReadRecordResult::InputEmpty => {}
+                ReadRecordResult::OutputFull | ReadRecordResult::OutputEndsFull if self.used + self.ended >= self.max_row_bytes => {
+                    self.flush(false, each)
+                }
+                ReadRecordResult::OutputFull => self.fields.resize(self.fields.len() * 2, 0),
+                ReadRecordResult::OutputEndsFull => self.ends.resize(self.ends.len() * 2, 0),
+                ReadRecordResult::Record => self.flush(true, each),
+                ReadRecordResult::End => return,
+            }
+        }
+    }
+
+    /// Hands over what has been read of the current row; the rest of it
+    /// follows unless the row is `complete`.
+    fn flush<F: FnMut(ScannedContent)>(&mut self, complete: bool, each: &mut F) {
+        let mut values = Vec::new();
+        let mut from = 0;
+        for (i, end) in self.ends[..self.ended].iter().enumerate() {
+            let end = end - self.flushed;
+            values.push((self.column + i as u32, String::from_utf8_lossy(&self.fields[from..end]).into_owned()));
+            from = end;
+        }
+        if !complete {
+            // The field in progress carries on in the next piece
+            values.push((self.column + self.ended as u32, String::from_utf8_lossy(&self.fields[from..self.used]).into_owned()));
+        }
+
+        let header = self.header.as_deref().filter(|_| self.row > 0);
+        if let Some(mut content) = row_content(ContentOrigin::default(), self.row, values, self.separator, header) {
+            content.start = self.start;
+            if self.row == 0 && self.header.is_none() {
+                self.header = Some(content.text[..content.owned_len].to_string());
+            }
+            each(content);
+        }
+
+        if complete {
+            self.row += 1;
+            self.column = 0;
+            self.flushed = 0;
+        } else {
+            self.column += self.ended as u32;
+            self.flushed += self.used;
+        }
+        self.used = 0;
+        self.ended = 0;
+        self.start = TextPosition {
+            offset: self.consumed,
+            line: self.reader.line() as usize - 1,
+            column: 0,
+        };
+    }
+}
+
+/// Zero-based row and column to an A1-style reference, e.g. `(6, 27)` to `AB7`.
+pub fn cell_reference(row: u32, col: u32) -> String {
+    let mut letters = Vec::new();
+    let mut col = col + 1;
+    while col > 0 {
+        let rem = (col - 1) % 26;
+        letters.push((b'A' + rem as u8) as char);
+        col = (col - 1) / 26;
+    }
+    letters.iter().rev().collect::<String>() + &(row + 1).to_string()
+}
@@ -0,0 +1,217 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// A directory to watch, and which files under it to scan.
+///
+/// Patterns without a `/` are matched against file names, e.g. `*.docx`;
+/// patterns with one against the path relative to the root, e.g.
+/// `finance/**/*.xlsx`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WatchRoot {
+    pub path: PathBuf,
+    /// Only files matching one of these are scanned; empty scans everything.
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Files matching any of these are never scanned.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl WatchRoot {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+/// Limits applied to files under every watch root.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ScopeConfig {
+    /// Directory names skipped wherever they appear, along with everything
+    /// below them.
+    pub ignored_dirs: Vec<String>,
+    /// Files larger than this many bytes are skipped.
+    pub max_file_size: Option<u64>,
+    /// MIME types to scan, e.g. `application/pdf` or `text/*`; empty scans
+    /// every type. Archives need their own type listed, e.g.
+    /// `application/zip`.
+    pub allowed_mime_types: Vec<String>,
+}
+
+impl Default for ScopeConfig {
+    fn default() -> Self {
+        Self {
+            ignored_dirs: vec![".git".to_string(), "node_modules".to_string()],
+            max_file_size: None,
+            allowed_mime_types: Vec::new(),
+        }
+    }
+}
+
+struct Patterns {
+    names: GlobSet,
+    paths: GlobSet,
+}
+
+impl Patterns {
+    fn new(patterns: &[String]) -> Result<Self, String> {
+        let mut names = GlobSetBuilder::new();
+        let mut paths = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| format!("invalid pattern {:?}: {}", pattern, e))?;
+            if pattern.contains('/') {
+                paths.add(glob);
+            } else {
+                names.add(glob);
+            }
+        }
+        Ok(Self {
+            names: names.build().map_err(|e| e.to_string())?,
+            paths: paths.build().map_err(|e| e.to_string())?,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.paths.is_empty()
+    }
+
+    fn matches(&self, relative: &Path) -> bool {
+        relative.file_name().is_some_and(|name| self.names.is_match(name)) || self.paths.is_match(relative)
+    }
+}
+
+struct RootScope {
+    path: PathBuf,
+    include: Patterns,
+    exclude: Patterns,
+}
+
+/// Compiled watch roots and scope limits, deciding which paths are
+/// watched, crawled and scanned.
+#[derive(Default)]
+pub struct FileScope {
+    roots: Vec<RootScope>,
+    ignored_dirs: HashSet<String>,
+    max_file_size: Option<u64>,
+    allowed_mime_types: Vec<String>,
+}
+
+impl FileScope {
+    /// Compiles `roots` and `config`. Roots must exist; each is
+    /// canonicalized so it matches the paths file events carry.
+    pub fn new(roots: &[WatchRoot], config: &ScopeConfig) -> Result<Self, Box<dyn Error>> {
+        let mut compiled = Vec::new();
+        for root in roots {
+            let path = std::fs::canonicalize(&root.path).map_err(|e| format!("{}: {}", root.path.display(), e))?;
+            let context = |e: String| format!("{}: {}", root.path.display(), e);
+            compiled.push(RootScope {
+                path,
+                include: Patterns::new(&root.include).map_err(context)?,
+                exclude: Patterns::new(&root.exclude).map_err(context)?,
+            });
+        }
+        // Nested roots: the innermost one decides
+        compiled.sort_by_key(|root| std::cmp::Reverse(root.path.components().count()));
+
+        Ok(Self {
+            roots: compiled,
+            ignored_dirs: config.ignored_dirs.iter().cloned().collect(),
+            max_file_size: config.max_file_size,
+            allowed_mime_types: config.allowed_mime_types.iter().map(|m| m.to_ascii_lowercase()).collect(),
+        })
+    }
+
+    /// The canonical root directories.
+    pub fn roots(&self) -> Vec<PathBuf> {
+        let mut roots: Vec<PathBuf> = self.roots.iter().map(|root| root.path.clone()).collect();
+        roots.sort();
+        roots
+    }
+
+    /// Whether a directory should be descended into.
+    pub fn allows_dir(&self, path: &Path) -> bool {
+        let (root, relative) = self.locate(path);
+        !self.is_ignored(relative.components())
+            && !root.is_some_and(|root| !relative.as_os_str().is_empty() && root.exclude.matches(relative))
+    }
+
+    /// Whether a file is in scope by its path: not under an ignored
+    /// directory, not excluded, and included if its root lists includes.
+    pub fn allows_file(&self, path: &Path) -> bool {
+        let (root, relative) = self.locate(path);
+        if self.is_ignored(relative.parent().into_iter().flat_map(Path::components)) {
+            return false;
+        }
+        match root {
+            Some(root) => !root.exclude.matches(relative) && (root.include.is_empty() || root.include.matches(relative)),
+            None => true,
+        }
+    }
+
+    /// Checks the size and type limits, returning why a file is skipped.
+    pub fn check_content(&self, path: &Path) -> Result<(), String> {
+        if let Some(max) = self.max_file_size {
+            let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            if size > max {
+                return Err(format!("{} bytes is over the {} byte limit", size, max));
+            }
+        }
+        if self.allowed_mime_types.is_empty() {
+            return Ok(());
+        }
+
+        let mime = detect_mime(path);
+        let allowed = self.allowed_mime_types.iter().any(|allowed| match allowed.strip_suffix("/*") {
+            Some(family) => mime.split('/').next() == Some(family),
+            None => *allowed == mime,
+        });
+        if allowed {
+            Ok(())
+        } else {
+            Err(format!("type {} is not allowed", mime))
+        }
+    }
+
+    /// The root `path` is under, and `path` relative to it. Paths outside
+    /// every root are returned whole.
+    fn locate<'a>(&self, path: &'a Path) -> (Option<&RootScope>, &'a Path) {
+        for root in &self.roots {
+            if let Ok(relative) = path.strip_prefix(&root.path) {
+                return (Some(root), relative);
+            }
+        }
+        (None, path)
+    }
+
+    fn is_ignored<'a>(&self, mut components: impl Iterator<Item = Component<'a>>) -> bool {
+        components.any(|c| matches!(c, Component::Normal(name) if name.to_str().is_some_and(|n| self.ignored_dirs.contains(n))))
+    }
+}
+
+/// MIME type from the file's magic bytes. Files without a recognisable
+/// signature are `text/plain` if they look like text, and
+/// `application/octet-stream` otherwise.
+fn detect_mime(path: &Path) -> String {
+    if let Ok(Some(kind)) = infer::get_from_path(path) {
+        return kind.mime_type().to_string();
+    }
+
+    let mut head = Vec::new();
+    let read = std::fs::File::open(path).and_then(|file| file.take(8192).read_to_end(&mut head));
+    if read.is_ok() && !head.contains(&0) {
+        "text/plain".to_string()
+    } else {
+        "application/octet-stream".to_string()
+    }
+}
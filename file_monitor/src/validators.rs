@@ -0,0 +1,311 @@
+use serde::{Serialize, Deserialize};
+
+/// Post-match checks that reject values which merely look like an identifier
+/// but cannot be a real one.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Validator {
+    Luhn,
+    PaymentCard,
+    UsSsn,
+    Iban,
+    /// IBAN from an EU member state, with that country's exact length.
+    EuIban,
+    Aadhaar,
+    Gstin,
+    UkNino,
+    DeIdCard,
+    FrNir,
+    /// Spanish DNI or NIE control letter.
+    EsDni,
+}
+
+impl Validator {
+    pub fn validate(&self, value: &str) -> bool {
+        match self {
+            Validator::Luhn => luhn_valid(&digits(value)),
+            Validator::PaymentCard => card_brand(value).is_some(),
+            Validator::UsSsn => ssn_valid(value),
+            Validator::Iban => iban_valid(value),
+            Validator::EuIban => eu_iban_valid(value),
+            Validator::Aadhaar => aadhaar_valid(value),
+            Validator::Gstin => gstin_valid(value),
+            Validator::UkNino => nino_valid(value),
+            Validator::DeIdCard => de_id_card_valid(value),
+            Validator::FrNir => fr_nir_valid(value),
+            Validator::EsDni => es_dni_valid(value),
+        }
+    }
+
+    /// Extra context worth attaching to a finding that passed validation.
+    pub fn describe(&self, value: &str) -> Option<(&'static str, String)> {
+        match self {
+            Validator::PaymentCard => card_brand(value).map(|brand| ("card_brand", brand.to_string())),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CardBrand {
+    Visa,
+    Mastercard,
+    Amex,
+    Discover,
+    DinersClub,
+    Jcb,
+    UnionPay,
+    Maestro,
+}
+
+impl std::fmt::Display for CardBrand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CardBrand::Visa => "Visa",
+            CardBrand::Mastercard => "Mastercard",
+            CardBrand::Amex => "American Express",
+            CardBrand::Discover => "Discover",
+            CardBrand::DinersClub => "Diners Club",
+            CardBrand::Jcb => "JCB",
+            CardBrand::UnionPay => "UnionPay",
+            CardBrand::Maestro => "Maestro",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+fn digits(value: &str) -> Vec<u32> {
+    value.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+pub fn luhn_valid(digits: &[u32]) -> bool {
+    if digits.len() < 2 {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                d
+            }
+        })
+        .sum();
+
+    sum.is_multiple_of(10)
+}
+
+/// Identifies the card network from the IIN prefix and length, returning
+/// `None` unless the number also passes the Luhn check.
+pub fn card_brand(value: &str) -> Option<CardBrand> {
+    let digits = digits(value);
+    if !luhn_valid(&digits) {
+        return None;
+    }
+
+    let len = digits.len();
+    let prefix = |n: usize| digits.iter().take(n).fold(0u32, |acc, d| acc * 10 + d);
+
+    let brand = match (prefix(1), prefix(2), prefix(3), prefix(4)) {
+        (4, _, _, _) if matches!(len, 13 | 16 | 19) => CardBrand::Visa,
+        (_, 51..=55, _, _) | (_, _, _, 2221..=2720) if len == 16 => CardBrand::Mastercard,
+        (_, 34 | 37, _, _) if len == 15 => CardBrand::Amex,
+        (_, 65, _, _) | (_, _, 644..=649, _) | (_, _, _, 6011) if (16..=19).contains(&len) => CardBrand::Discover,
+        (_, 36 | 38 | 39, _, _) | (_, _, 300..=305, _) if (14..=19).contains(&len) => CardBrand::DinersClub,
+        (_, _, _, 3528..=3589) if (16..=19).contains(&len) => CardBrand::Jcb,
+        (_, 62, _, _) if (16..=19).contains(&len) => CardBrand::UnionPay,
+        (_, 50 | 56..=58, _, _) | (_, _, _, 6304 | 6759 | 6761..=6763) if (12..=19).contains(&len) => CardBrand::Maestro,
+        _ => return None,
+    };
+
+    Some(brand)
+}
+
+/// Applies the SSA allocation rules: no 000, 666 or 9xx area, no 00 group,
+/// no 0000 serial, and none of the numbers famously used in advertising.
+pub fn ssn_valid(value: &str) -> bool {
+    let digits = digits(value);
+    if digits.len() != 9 {
+        return false;
+    }
+
+    let area = digits[0] * 100 + digits[1] * 10 + digits[2];
+    let group = digits[3] * 10 + digits[4];
+    let serial = digits[5] * 1000 + digits[6] * 100 + digits[7] * 10 + digits[8];
+
+    if area == 0 || area == 666 || area >= 900 || group == 0 || serial == 0 {
+        return false;
+    }
+
+    !matches!((area, group, serial), (78, 5, 1120) | (219, 9, 9999))
+}
+
+/// ISO 13616 check: move the country code and check digits to the end,
+/// expand letters to numbers and require the result mod 97 to equal 1.
+pub fn iban_valid(value: &str) -> bool {
+    let iban: Vec<char> = value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    if iban.len() < 15 || iban.len() > 34 {
+        return false;
+    }
+    if !iban[..2].iter().all(|c| c.is_ascii_uppercase()) || !iban[2..4].iter().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+
+    let mut remainder = 0u32;
+    for c in iban[4..].iter().chain(iban[..4].iter()) {
+        let value = match c.to_digit(36) {
+            Some(v) => v,
+            None => return false,
+        };
+        remainder = if value > 9 {
+            (remainder * 100 + value) % 97
+        } else {
+            (remainder * 10 + value) % 97
+        };
+    }
+
+    remainder == 1
+}
+
+/// IBAN lengths of the EU member states.
+const EU_IBAN_LENGTHS: [(&str, usize); 27] = [
+    ("AT", 20), ("BE", 16), ("BG", 22), ("HR", 21), ("CY", 28), ("CZ", 24), ("DK", 18),
+    ("EE", 20), ("FI", 18), ("FR", 27), ("DE", 22), ("GR", 27), ("HU", 28), ("IE", 22),
+    ("IT", 27), ("LV", 21), ("LT", 20), ("LU", 20), ("MT", 31), ("NL", 18), ("PL", 28),
+    ("PT", 25), ("RO", 24), ("SK", 24), ("SI", 19), ("ES", 24), ("SE", 24),
+];
+
+pub fn eu_iban_valid(value: &str) -> bool {
+    let iban: String = value.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+    let country_len = EU_IBAN_LENGTHS.iter().find(|(country, _)| iban.starts_with(country)).map(|(_, len)| *len);
+    country_len == Some(iban.len()) && iban_valid(&iban)
+}
+
+const VERHOEFF_D: [[usize; 10]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+    [1, 2, 3, 4, 0, 6, 7, 8, 9, 5],
+    [2, 3, 4, 0, 1, 7, 8, 9, 5, 6],
+    [3, 4, 0, 1, 2, 8, 9, 5, 6, 7],
+    [4, 0, 1, 2, 3, 9, 5, 6, 7, 8],
+    [5, 9, 8, 7, 6, 0, 4, 3, 2, 1],
+    [6, 5, 9, 8, 7, 1, 0, 4, 3, 2],
+    [7, 6, 5, 9, 8, 2, 1, 0, 4, 3],
+    [8, 7, 6, 5, 9, 3, 2, 1, 0, 4],
+    [9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
+];
+
+const VERHOEFF_P: [[usize; 10]; 8] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+    [1, 5, 7, 6, 2, 8, 3, 0, 9, 4],
+    [5, 8, 0, 3, 7, 9, 6, 1, 4, 2],
+    [8, 9, 1, 6, 0, 4, 3, 5, 2, 7],
+    [9, 4, 5, 3, 1, 2, 6, 8, 7, 0],
+    [4, 2, 8, 6, 5, 7, 3, 9, 0, 1],
+    [2, 7, 9, 3, 8, 0, 6, 4, 1, 5],
+    [7, 0, 4, 6, 9, 1, 3, 2, 5, 8],
+];
+
+pub fn verhoeff_valid(digits: &[u32]) -> bool {
+    let check = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .fold(0, |c, (i, &d)| VERHOEFF_D[c][VERHOEFF_P[i % 8][d as usize]]);
+    !digits.is_empty() && check == 0
+}
+
+/// Twelve digits, not starting with 0 or 1, ending in a Verhoeff check digit.
+pub fn aadhaar_valid(value: &str) -> bool {
+    let digits = digits(value);
+    digits.len() == 12 && digits[0] >= 2 && verhoeff_valid(&digits)
+}
+
+/// State code 01-38 (or 97 and 99 for other territories and the Centre)
+/// followed by the holder's PAN, and a base-36 check character over the
+/// first fourteen.
+pub fn gstin_valid(value: &str) -> bool {
+    let chars: Vec<u32> = match value.chars().map(|c| c.to_digit(36)).collect::<Option<Vec<_>>>() {
+        Some(chars) if chars.len() == 15 => chars,
+        _ => return false,
+    };
+    let state = match value[..2].parse::<u32>() {
+        Ok(state) if value[..2].bytes().all(|b| b.is_ascii_digit()) => state,
+        _ => return false,
+    };
+    if !(1..=38).contains(&state) && state != 97 && state != 99 {
+        return false;
+    }
+
+    let sum: u32 = chars[..14]
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| {
+            let product = v * if i % 2 == 0 { 1 } else { 2 };
+            product / 36 + product % 36
+        })
+        .sum();
+    (36 - sum % 36) % 36 == chars[14]
+}
+
+/// HMRC never issues these prefixes.
+pub fn nino_valid(value: &str) -> bool {
+    let nino: String = value.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+    nino.is_ascii() && nino.len() == 9 && !["BG", "GB", "KN", "NK", "NT", "TN", "ZZ"].contains(&&nino[..2])
+}
+
+/// Personalausweis serial: nine characters weighted 7-3-1 (letters count
+/// from A = 10) and a trailing check digit.
+pub fn de_id_card_valid(value: &str) -> bool {
+    let chars: Vec<u32> = match value.chars().map(|c| c.to_digit(36)).collect::<Option<Vec<_>>>() {
+        Some(chars) if chars.len() == 10 => chars,
+        _ => return false,
+    };
+    let sum: u32 = chars[..9].iter().zip([7, 3, 1].iter().cycle()).map(|(v, w)| v * w).sum();
+    sum % 10 == chars[9]
+}
+
+/// INSEE number: the two-digit key is 97 minus the first thirteen digits
+/// mod 97, with Corsican departments 2A and 2B counted as 19 and 18.
+pub fn fr_nir_valid(value: &str) -> bool {
+    let nir: String = value.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+    if !nir.is_ascii() || nir.len() != 15 {
+        return false;
+    }
+    let body = match &nir[5..7] {
+        "2A" => format!("{}19{}", &nir[..5], &nir[7..13]),
+        "2B" => format!("{}18{}", &nir[..5], &nir[7..13]),
+        _ => nir[..13].to_string(),
+    };
+    match (body.parse::<u64>(), nir[13..].parse::<u64>()) {
+        (Ok(body), Ok(key)) => 97 - body % 97 == key,
+        _ => false,
+    }
+}
+
+/// The control letter indexes the number mod 23; NIEs map X, Y and Z to 0-2.
+pub fn es_dni_valid(value: &str) -> bool {
+    let id: String = value.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_ascii_uppercase();
+    let (number, letter) = match id.char_indices().last() {
+        Some((i, letter)) if letter.is_ascii_alphabetic() => (&id[..i], letter),
+        _ => return false,
+    };
+    let number = match number.chars().next() {
+        Some('X') => format!("0{}", &number[1..]),
+        Some('Y') => format!("1{}", &number[1..]),
+        Some('Z') => format!("2{}", &number[1..]),
+        _ => number.to_string(),
+    };
+    match number.parse::<usize>() {
+        Ok(n) if number.len() == 8 => "TRWAGMYFPDXBNJZSQVHLCKE".as_bytes()[n % 23] as char == letter,
+        _ => false,
+    }
+}
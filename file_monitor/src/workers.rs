@@ -0,0 +1,153 @@
+use serde::{Serialize, Deserialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How many files are scanned at once and how much work may pile up.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct WorkerConfig {
+    /// Files scanned concurrently.
+    pub max_workers: usize,
+    /// Files waiting for a worker. Once full, further files stay with the
+    /// debouncer or the baseline crawl until a worker frees up.
+    pub max_queued: usize,
+    /// A scan still running after this long is abandoned and reported.
+    pub timeout_secs: u64,
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self {
+            max_workers: 4,
+            max_queued: 1000,
+            timeout_secs: 300,
+        }
+    }
+}
+
+impl WorkerConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+/// A file waiting to be scanned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Job {
+    path: PathBuf,
+    /// Files are compared by the power of two their size rounds up to, so
+    /// recency decides between files of a similar size.
+    size_class: u32,
+    modified: Option<SystemTime>,
+    seq: u64,
+}
+
+impl Ord for Job {
+    /// Greatest first out of the heap: smaller size class, then more
+    /// recently modified, then first queued.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .size_class
+            .cmp(&self.size_class)
+            .then(self.modified.cmp(&other.modified))
+            .then(other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Files waiting for a scan worker, small and recently modified files
+/// first. A path is queued at most once, and is not handed out again while
+/// a worker is still scanning it.
+pub struct ScanQueue {
+    capacity: usize,
+    heap: BinaryHeap<Job>,
+    queued: HashSet<PathBuf>,
+    running: HashSet<PathBuf>,
+    next_seq: u64,
+}
+
+impl ScanQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            heap: BinaryHeap::new(),
+            queued: HashSet::new(),
+            running: HashSet::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Queues `path` unless it is already waiting. Returns false if the
+    /// queue is full.
+    pub fn push(&mut self, path: PathBuf) -> bool {
+        if self.queued.contains(&path) {
+            return true;
+        }
+        if self.is_full() {
+            return false;
+        }
+
+        let metadata = std::fs::metadata(&path).ok();
+        let size = metadata.as_ref().map_or(0, |m| m.len());
+        self.heap.push(Job {
+            size_class: u64::BITS - size.leading_zeros(),
+            modified: metadata.and_then(|m| m.modified().ok()),
+            seq: self.next_seq,
+            path: path.clone(),
+        });
+        self.next_seq += 1;
+        self.queued.insert(path);
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.space() == 0
+    }
+
+    /// Files that can be queued before the queue is full.
+    pub fn space(&self) -> usize {
+        self.capacity.saturating_sub(self.heap.len())
+    }
+
+    /// Paths handed out and not yet `done`.
+    pub fn running(&self) -> usize {
+        self.running.len()
+    }
+
+    /// The next file to scan, marked as running until `done` is called.
+    pub fn pop(&mut self) -> Option<PathBuf> {
+        let mut busy = Vec::new();
+        let next = loop {
+            match self.heap.pop() {
+                Some(job) if self.running.contains(&job.path) => busy.push(job),
+                Some(job) => break Some(job.path),
+                None => break None,
+            }
+        };
+        self.heap.extend(busy);
+
+        let path = next?;
+        self.queued.remove(&path);
+        self.running.insert(path.clone());
+        Some(path)
+    }
+
+    pub fn done(&mut self, path: &Path) {
+        self.running.remove(path);
+    }
+}
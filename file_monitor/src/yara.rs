@@ -0,0 +1,253 @@
+use boreal::compiler::AddRuleError;
+use boreal::scanner::{EvaluatedRule, ScanParams};
+use boreal::{Compiler, MetadataValue, Scanner};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+use crate::cache::digest;
+use crate::detectors::Severity;
+
+/// Longest a single YARA evaluation may run before its partial results are
+/// used.
+const SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pieces of text waiting for a `YaraTask` before `scan_text` blocks.
+const PENDING_PIECES: usize = 4;
+
+/// A compiled set of YARA rules.
+#[derive(Clone)]
+pub struct YaraRules {
+    scanner: Scanner,
+    digest: String,
+}
+
+/// A rule that matched, with the details alerts carry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct YaraMatch {
+    pub rule: String,
+    pub namespace: String,
+    pub tags: Vec<String>,
+    /// Rule metadata, with strings, integers and booleans as text.
+    pub metadata: BTreeMap<String, String>,
+    /// Identifiers of the strings that matched, e.g. `$header`.
+    pub strings: Vec<String>,
+    /// Bytes of the first string match.
+    pub first_match: Vec<u8>,
+    pub match_count: usize,
+}
+
+impl YaraMatch {
+    /// Severity from the rule's `severity` metadata, if it names one.
+    pub fn severity(&self) -> Option<Severity> {
+        match self.metadata.get("severity")?.to_ascii_lowercase().as_str() {
+            "low" => Some(Severity::Low),
+            "medium" => Some(Severity::Medium),
+            "high" => Some(Severity::High),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// Rule matches across everything evaluated for one file, merged per rule
+/// and remembering what was scanned ("bytes", "text") when it matched.
+#[derive(Default)]
+pub(crate) struct YaraHits {
+    rules: BTreeMap<(String, String), (YaraMatch, BTreeSet<&'static str>)>,
+}
+
+impl YaraHits {
+    pub(crate) fn add(&mut self, matches: Vec<YaraMatch>, target: &'static str) {
+        for m in matches {
+            let key = (m.namespace.clone(), m.rule.clone());
+            match self.rules.get_mut(&key) {
+                Some((existing, targets)) => {
+                    existing.match_count += m.match_count;
+                    // Matched only in an overlap so far
+                    if existing.first_match.is_empty() {
+                        existing.first_match = m.first_match;
+                        existing.strings = m.strings;
+                    }
+                    targets.insert(target);
+                }
+                None => {
+                    self.rules.insert(key, (m, BTreeSet::from([target])));
+                }
+            }
+        }
+    }
+
+    pub(crate) fn into_matches(self) -> impl Iterator<Item = (YaraMatch, BTreeSet<&'static str>)> {
+        self.rules.into_values()
+    }
+}
+
+/// Evaluates rules for one file on a blocking thread: first the start of
+/// the file's raw bytes, then each piece of text handed over as it is
+/// extracted.
+pub(crate) struct YaraTask {
+    text: mpsc::SyncSender<(String, usize)>,
+    handle: tokio::task::JoinHandle<Result<YaraHits, String>>,
+}
+
+impl YaraTask {
+    pub(crate) fn spawn(rules: YaraRules, path: PathBuf, max_bytes: u64) -> Self {
+        let (text, pieces) = mpsc::sync_channel::<(String, usize)>(PENDING_PIECES);
+        let handle = tokio::task::spawn_blocking(move || {
+            let mut hits = YaraHits::default();
+            let mut raw = Vec::new();
+            std::fs::File::open(&path)
+                .and_then(|file| file.take(max_bytes).read_to_end(&mut raw))
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            hits.add(rules.scan(&raw), "bytes");
+            drop(raw);
+
+            for (piece, owned_len) in pieces {
+                hits.add(rules.scan_piece(piece.as_bytes(), owned_len), "text");
+            }
+            Ok(hits)
+        });
+        Self { text, handle }
+    }
+
+    /// Hands over a piece of text, blocking while the task is
+    /// `PENDING_PIECES` behind so a slow rule set cannot queue a whole file.
+    /// Matches starting at or after `owned_len` are counted with the next
+    /// piece, which repeats them.
+    pub(crate) fn scan_text(&self, text: &str, owned_len: usize) {
+        // Only fails once the task has given up on an unreadable file,
+        // which `finish` reports
+        let _ = self.text.send((text.to_string(), owned_len));
+    }
+
+    /// Waits for everything handed over so far to be evaluated.
+    pub(crate) async fn finish(self) -> Result<YaraHits, Box<dyn Error>> {
+        drop(self.text);
+        Ok(self.handle.await??)
+    }
+}
+
+impl YaraRules {
+    /// Compiles every `.yar` and `.yara` file under `dir`. Each file gets its
+    /// own namespace, named after its path relative to `dir`, so rule names
+    /// only need to be unique within a file. All compile errors are reported
+    /// together.
+    pub fn compile_dir(dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut files = Vec::new();
+        collect_rule_files(dir, &mut files)?;
+        files.sort();
+
+        let mut compiler = Compiler::new();
+        let mut errors = Vec::new();
+        let mut sources = Vec::new();
+        for file in &files {
+            let namespace = file.strip_prefix(dir).unwrap_or(file).to_string_lossy().replace('\\', "/");
+            sources.extend_from_slice(namespace.as_bytes());
+            sources.extend_from_slice(&std::fs::read(file).unwrap_or_default());
+            if let Err(e) = compiler.add_rules_file_in_namespace(file, &namespace) {
+                errors.push(describe(&e, file));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors.join("\n").into());
+        }
+        if files.is_empty() {
+            return Err(format!("{}: no .yar or .yara files found", dir.display()).into());
+        }
+        Ok(Self::finalize(compiler, &sources))
+    }
+
+    pub fn from_source(source: &str) -> Result<Self, Box<dyn Error>> {
+        let mut compiler = Compiler::new();
+        compiler.add_rules_str(source).map_err(|e| e.to_string())?;
+        Ok(Self::finalize(compiler, source.as_bytes()))
+    }
+
+    fn finalize(compiler: Compiler, sources: &[u8]) -> Self {
+        let mut scanner = compiler.finalize();
+        scanner.set_scan_params(ScanParams::default().timeout_duration(Some(SCAN_TIMEOUT)));
+        Self { scanner, digest: digest(sources) }
+    }
+
+    /// Digest of the rule sources these rules were compiled from.
+    pub fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.scanner.rules().count()
+    }
+
+    /// Rules matching `data`. A scan that times out still reports the rules
+    /// that had matched by then.
+    pub fn scan(&self, data: &[u8]) -> Vec<YaraMatch> {
+        self.scan_piece(data, data.len())
+    }
+
+    /// Like `scan`, for a piece of a file whose last bytes from `owned_len`
+    /// on are repeated by the next piece: string matches starting there are
+    /// left for that piece to count.
+    pub fn scan_piece(&self, data: &[u8], owned_len: usize) -> Vec<YaraMatch> {
+        let result = match self.scanner.scan_mem(data) {
+            Ok(result) => result,
+            Err((e, partial)) => {
+                eprintln!("YARA scan incomplete: {:?}", e);
+                partial
+            }
+        };
+        result.rules.iter().map(|rule| self.describe_match(rule, owned_len)).collect()
+    }
+
+    fn describe_match(&self, rule: &EvaluatedRule, owned_len: usize) -> YaraMatch {
+        let metadata = rule
+            .metadatas
+            .iter()
+            .map(|meta| {
+                let value = match meta.value {
+                    MetadataValue::Bytes(bytes) => String::from_utf8_lossy(self.scanner.get_bytes_symbol(bytes)).to_string(),
+                    MetadataValue::Integer(n) => n.to_string(),
+                    MetadataValue::Boolean(b) => b.to_string(),
+                };
+                (self.scanner.get_string_symbol(meta.name).to_string(), value)
+            })
+            .collect();
+        let matched: Vec<_> = rule
+            .matches
+            .iter()
+            .map(|s| (format!("${}", s.name), s.matches.iter().filter(|m| m.offset < owned_len).collect::<Vec<_>>()))
+            .filter(|(_, matches)| !matches.is_empty())
+            .collect();
+
+        YaraMatch {
+            rule: rule.name.to_string(),
+            namespace: rule.namespace.to_string(),
+            tags: rule.tags.iter().map(|&tag| self.scanner.get_string_symbol(tag).to_string()).collect(),
+            metadata,
+            strings: matched.iter().map(|(name, _)| name.clone()).collect(),
+            first_match: matched.first().map(|(_, matches)| matches[0].data.to_vec()).unwrap_or_default(),
+            match_count: matched.iter().map(|(_, matches)| matches.len()).sum(),
+        }
+    }
+}
+
+fn collect_rule_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    for entry in std::fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_rule_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "yar" || ext == "yara") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// `path:line:column: message`, as the YARA command line tools print it.
+fn describe(error: &AddRuleError, path: &Path) -> String {
+    let source = std::fs::read_to_string(path).unwrap_or_default();
+    boreal::compiler::generate_description(&error.to_diagnostic(), &path.to_string_lossy(), &source)
+}
@@ -0,0 +1,204 @@
+use file_monitor::config::{ArchiveLimits, ScannerConfig};
+use file_monitor::ContentScanner;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::write::FileOptions;
+
+fn tar_bytes(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut tar = tar::Builder::new(Vec::new());
+    for (name, content) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        tar.append_data(&mut header, name, *content).unwrap();
+    }
+    tar.into_inner().unwrap()
+}
+
+fn gzip_bytes(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn zip_bytes(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in entries {
+        zip.start_file(*name, FileOptions::default()).unwrap();
+        zip.write_all(content).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+#[tokio::test]
+async fn test_nested_archives_are_scanned_with_entry_paths() {
+    let dir = Path::new("test_archive_nested");
+    fs::create_dir_all(dir).unwrap();
+    let docx = zip_bytes(&[("word/document.xml", br#"<w:document xmlns:w="w"><w:p><w:r><w:t>SSN 123-45-6789</w:t></w:r></w:p></w:document>"#)]);
+    let inner = zip_bytes(&[("notes.txt", b"password=hunter2"), ("report.docx", &docx)]);
+    fs::write(dir.join("outer.zip"), zip_bytes(&[("inner.zip", &inner)])).unwrap();
+
+    let output = ContentScanner::new().scan(&dir.join("outer.zip")).await.unwrap();
+    let entries: Vec<&str> = output.contents.iter().filter_map(|c| c.origin.entry.as_deref()).collect();
+
+    assert!(output.violations.is_empty());
+    assert!(entries.contains(&"inner.zip/notes.txt"));
+    assert!(entries.contains(&"inner.zip/report.docx/word/document.xml"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_archive_limits_raise_violations() {
+    let dir = Path::new("test_archive_limits");
+    fs::create_dir_all(dir).unwrap();
+    let zeros = vec![0u8; 4 * 1024 * 1024];
+    fs::write(dir.join("bomb.zip"), zip_bytes(&[("zeros.bin", &zeros)])).unwrap();
+    let nested = zip_bytes(&[("deep.zip", &zip_bytes(&[("secret.txt", b"password=hunter2")]))]);
+    fs::write(dir.join("deep.zip"), nested).unwrap();
+
+    let scanner = ContentScanner::with_config(ScannerConfig {
+        archive: ArchiveLimits {
+            max_depth: 1,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    let bomb = scanner.scan(&dir.join("bomb.zip")).await.unwrap();
+    assert_eq!(bomb.violations.len(), 1);
+    assert!(bomb.violations[0].reason.contains("compression ratio"));
+
+    let deep = scanner.scan(&dir.join("deep.zip")).await.unwrap();
+    assert_eq!(deep.violations.len(), 1);
+    assert_eq!(deep.violations[0].origin.entry.as_deref(), Some("deep.zip"));
+    assert!(deep.contents.is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_compressed_tarballs_are_scanned_per_entry() {
+    let dir = Path::new("test_archive_tar");
+    fs::create_dir_all(dir).unwrap();
+    let inner = zip_bytes(&[("notes.txt", b"password=hunter2")]);
+    let tar = tar_bytes(&[("docs/readme.txt", b"contact alice@example.com"), ("docs/inner.zip", &inner)]);
+
+    let mut bzip2 = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+    bzip2.write_all(&tar).unwrap();
+    let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+    xz.write_all(&tar).unwrap();
+
+    let files = [
+        ("plain.tar", tar.clone()),
+        ("backup.tar.gz", gzip_bytes(&tar)),
+        ("backup.tbz2", bzip2.finish().unwrap()),
+        ("backup.tar.xz", xz.finish().unwrap()),
+        ("backup.tar.zst", zstd::encode_all(&tar[..], 0).unwrap()),
+    ];
+    for (name, data) in files {
+        fs::write(dir.join(name), data).unwrap();
+
+        let output = ContentScanner::new().scan(&dir.join(name)).await.unwrap();
+        let entries: Vec<&str> = output.contents.iter().filter_map(|c| c.origin.entry.as_deref()).collect();
+
+        assert!(output.violations.is_empty(), "{}", name);
+        assert!(entries.contains(&"docs/readme.txt"), "{}: {:?}", name, entries);
+        assert!(entries.contains(&"docs/inner.zip/notes.txt"), "{}: {:?}", name, entries);
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_single_compressed_file_and_7z_archive() {
+    let dir = Path::new("test_archive_7z");
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("notes.txt.gz"), gzip_bytes(b"password=hunter2")).unwrap();
+    fs::write(dir.join("secret.txt"), b"api_key=abcdef123456").unwrap();
+
+    let mut sz = sevenz_rust::SevenZWriter::create(dir.join("bundle.7z")).unwrap();
+    let entry = sevenz_rust::SevenZArchiveEntry::from_path(dir.join("secret.txt"), "keys/secret.txt".to_string());
+    sz.push_archive_entry(entry, Some(fs::File::open(dir.join("secret.txt")).unwrap())).unwrap();
+    sz.finish().unwrap();
+
+    let gz = ContentScanner::new().scan(&dir.join("notes.txt.gz")).await.unwrap();
+    assert_eq!(gz.contents.len(), 1);
+    assert_eq!(gz.contents[0].origin.entry.as_deref(), Some("notes.txt"));
+    assert_eq!(gz.contents[0].text, "password=hunter2");
+
+    let sevenz = ContentScanner::new().scan(&dir.join("bundle.7z")).await.unwrap();
+    assert_eq!(sevenz.contents.len(), 1);
+    assert_eq!(sevenz.contents[0].origin.entry.as_deref(), Some("keys/secret.txt"));
+    assert_eq!(sevenz.contents[0].text, "api_key=abcdef123456");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_gzip_bomb_raises_violation() {
+    let dir = Path::new("test_archive_gzip_bomb");
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("zeros.bin.gz"), gzip_bytes(&vec![0u8; 4 * 1024 * 1024])).unwrap();
+
+    let output = ContentScanner::new().scan(&dir.join("zeros.bin.gz")).await.unwrap();
+    assert_eq!(output.violations.len(), 1);
+    assert!(output.violations[0].reason.contains("compression ratio"));
+    assert!(output.contents.is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// Rewrites the uncompressed size `name` declares in both its local header
+/// and the central directory.
+fn declare_size(mut zip: Vec<u8>, name: &str, size: u32) -> Vec<u8> {
+    let u16_at = |zip: &[u8], at: usize| u16::from_le_bytes([zip[at], zip[at + 1]]) as usize;
+    for at in 0..zip.len().saturating_sub(46) {
+        let (size_at, name_at, name_len) = match &zip[at..at + 4] {
+            b"PK\x03\x04" => (at + 22, at + 30, u16_at(&zip, at + 26)),
+            b"PK\x01\x02" => (at + 24, at + 46, u16_at(&zip, at + 28)),
+            _ => continue,
+        };
+        if zip.get(name_at..name_at + name_len) == Some(name.as_bytes()) {
+            zip[size_at..size_at + 4].copy_from_slice(&size.to_le_bytes());
+        }
+    }
+    zip
+}
+
+#[tokio::test]
+async fn test_documents_and_workbooks_that_understate_their_size() {
+    let dir = Path::new("test_archive_declared_size");
+    fs::create_dir_all(dir).unwrap();
+    let filler = "SSN 123-45-6789 ".repeat(512 * 1024);
+    let document = format!(r#"<w:document xmlns:w="w"><w:p><w:r><w:t>{}</w:t></w:r></w:p></w:document>"#, filler);
+    let docx = zip_bytes(&[("word/document.xml", document.as_bytes())]);
+    fs::write(dir.join("report.docx"), declare_size(docx, "word/document.xml", 1024)).unwrap();
+
+    let mut ods = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    ods.start_file("mimetype", FileOptions::default().compression_method(zip::CompressionMethod::Stored)).unwrap();
+    ods.write_all(b"application/vnd.oasis.opendocument.spreadsheet").unwrap();
+    ods.start_file("content.xml", FileOptions::default()).unwrap();
+    ods.write_all(filler.as_bytes()).unwrap();
+    let ods = ods.finish().unwrap().into_inner();
+    fs::write(dir.join("budget.ods"), declare_size(ods, "content.xml", 1024)).unwrap();
+
+    // Only the bytes actually inflated can trip the limit
+    let scanner = ContentScanner::with_config(ScannerConfig {
+        archive: ArchiveLimits {
+            max_expanded_bytes: 1024 * 1024,
+            max_compression_ratio: f64::MAX,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    for name in ["report.docx", "budget.ods"] {
+        let output = scanner.scan(&dir.join(name)).await.unwrap();
+        assert_eq!(output.violations.len(), 1, "{}", name);
+        assert!(output.violations[0].reason.contains("expanded bytes"), "{}: {}", name, output.violations[0].reason);
+        assert!(output.contents.is_empty(), "{}", name);
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
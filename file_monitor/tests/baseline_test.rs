@@ -0,0 +1,101 @@
+mod common;
+
+use file_monitor::baseline::next_crawl_in;
+use file_monitor::config::MonitorConfig;
+use file_monitor::{BaselineConfig, BaselineCrawl, BaselineState, Communication, FileMonitor};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+fn config(state_dir: &Path) -> BaselineConfig {
+    BaselineConfig {
+        enabled: true,
+        files_per_second: 0,
+        progress_every: 1,
+        state_dir: state_dir.to_path_buf(),
+        ..Default::default()
+    }
+}
+
+fn names(files: &[PathBuf], root: &Path) -> Vec<String> {
+    files.iter().map(|f| f.strip_prefix(root).unwrap().to_string_lossy().to_string()).collect()
+}
+
+#[test]
+fn test_interrupted_crawl_resumes_in_path_order() {
+    let dir = Path::new("test_baseline_resume");
+    let root = dir.join("root");
+    fs::create_dir_all(root.join("a")).unwrap();
+    for name in ["c.txt", "a/2.txt", "b.txt", "a/1.txt"] {
+        fs::write(root.join(name), "x").unwrap();
+    }
+    let config = config(&dir.join("state"));
+    assert_eq!(next_crawl_in(&root, &config, false), Some(Duration::ZERO));
+
+    // Scan two files, then stop as if the agent was killed
+    let mut crawl = BaselineCrawl::start(&root, &config);
+    let mut first = Vec::new();
+    for _ in 0..2 {
+        let file = crawl.next_file().unwrap();
+        crawl.completed(&file).unwrap();
+        first.push(file);
+    }
+    assert_eq!(names(&first, &root), vec!["a/1.txt", "a/2.txt"]);
+    drop(crawl);
+    assert_eq!(next_crawl_in(&root, &config, false), Some(Duration::ZERO));
+
+    let mut crawl = BaselineCrawl::start(&root, &config);
+    assert_eq!(crawl.progress(), "2/4 files (50.0%)");
+    let mut rest = Vec::new();
+    while let Some(file) = crawl.next_file() {
+        crawl.completed(&file).unwrap();
+        rest.push(file);
+    }
+    assert_eq!(names(&rest, &root), vec!["b.txt", "c.txt"]);
+    let state = crawl.finish().unwrap();
+    assert_eq!(state.files_scanned, 4);
+    assert_eq!(BaselineState::load(&root, &config), Some(state));
+
+    // A finished crawl runs again on the next start, or when the interval is up
+    assert_eq!(next_crawl_in(&root, &config, true), Some(Duration::ZERO));
+    assert_eq!(next_crawl_in(&root, &config, false), None);
+    let scheduled = BaselineConfig { interval_secs: Some(3600), ..config.clone() };
+    assert!(next_crawl_in(&root, &scheduled, false).unwrap() > Duration::from_secs(3500));
+    assert_eq!(BaselineCrawl::start(&root, &config).progress(), "0/4 files (0.0%)");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_throttle() {
+    let config = BaselineConfig { files_per_second: 4, ..Default::default() };
+    assert_eq!(config.throttle(), Some(Duration::from_millis(250)));
+    assert_eq!(BaselineConfig { files_per_second: 0, ..config }.throttle(), None);
+}
+
+#[tokio::test]
+async fn test_existing_files_are_scanned_at_startup() {
+    let dir = Path::new("test_baseline_startup");
+    let root = dir.join("root");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("old.txt"), "SSN: 123-45-6789").unwrap();
+
+    let comm = Communication::new("test".to_string(), common::mock_endpoint());
+    let alerts = comm.alerts.clone();
+    let monitor = FileMonitor::new(comm).with_config(MonitorConfig {
+        baseline: config(&dir.join("state")),
+        ..Default::default()
+    });
+    let watched = root.clone();
+    let handle = tokio::spawn(async move {
+        monitor.start_monitoring(&watched).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    assert!(alerts.lock().await.iter().any(|a| a.pattern_type == "US_SSN" && a.file_path.ends_with("old.txt")));
+    let state = BaselineState::load(&fs::canonicalize(&root).unwrap(), &config(&dir.join("state"))).unwrap();
+    assert!(state.completed_at.is_some());
+
+    handle.abort();
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,237 @@
+mod common;
+
+use file_monitor::cache::{CacheCheck, FileStamp};
+use file_monitor::config::MonitorConfig;
+use file_monitor::{CacheConfig, Communication, FileMonitor, ScanCache, ScopeConfig, WatchRoot, WorkerConfig};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+fn touch(path: &Path, seconds_later: u64) {
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(seconds_later)).unwrap();
+}
+
+#[test]
+fn test_unchanged_touched_and_copied_files() {
+    let dir = Path::new("test_cache_check");
+    fs::create_dir_all(dir).unwrap();
+    let report = dir.join("report.txt");
+    let copy = dir.join("copy.txt");
+    fs::write(&report, "quarterly numbers").unwrap();
+
+    let mut cache = ScanCache::load(&dir.join("cache.json"));
+    assert!(cache.is_empty());
+    let stamp = FileStamp::of(&report).unwrap();
+    let CacheCheck::Changed { content_hash } = cache.check(&report, stamp, "v1").unwrap() else {
+        panic!("new file should be scanned");
+    };
+    cache.insert(&report, stamp, content_hash.clone(), "v1", Vec::new());
+    assert!(matches!(cache.check(&report, stamp, "v1").unwrap(), CacheCheck::Unchanged));
+
+    // Touching a file changes its stamp but not its contents
+    touch(&report, 60);
+    let touched = FileStamp::of(&report).unwrap();
+    assert_ne!(touched, stamp);
+    assert!(matches!(cache.check(&report, touched, "v1").unwrap(), CacheCheck::Unchanged));
+    assert_eq!(cache.get(&report).unwrap().stamp, touched);
+
+    fs::copy(&report, &copy).unwrap();
+    match cache.check(&copy, FileStamp::of(&copy).unwrap(), "v1").unwrap() {
+        CacheCheck::Duplicate { of, content_hash: hash, .. } => {
+            assert_eq!(of, report);
+            assert_eq!(hash, content_hash);
+        }
+        other => panic!("expected a duplicate, got {:?}", other),
+    }
+
+    // New detectors invalidate every entry
+    assert!(matches!(cache.check(&report, touched, "v2").unwrap(), CacheCheck::Changed { .. }));
+    assert!(matches!(cache.check(&copy, FileStamp::of(&copy).unwrap(), "v2").unwrap(), CacheCheck::Changed { .. }));
+
+    fs::write(&report, "quarterly numbers, revised").unwrap();
+    touch(&report, 120);
+    assert!(matches!(cache.check(&report, FileStamp::of(&report).unwrap(), "v1").unwrap(), CacheCheck::Changed { .. }));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_hash_can_be_checked_separately() {
+    let dir = Path::new("test_cache_split");
+    fs::create_dir_all(dir).unwrap();
+    let report = dir.join("report.txt");
+    fs::write(&report, "quarterly numbers").unwrap();
+    let stamp = FileStamp::of(&report).unwrap();
+
+    let mut cache = ScanCache::load(&dir.join("cache.json"));
+    assert!(!cache.is_unchanged(&report, stamp, "v1"));
+    let content_hash = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".to_string();
+    let CacheCheck::Changed { content_hash } = cache.check_content(&report, stamp, content_hash, "v1") else {
+        panic!("new file should be scanned");
+    };
+    cache.insert(&report, stamp, content_hash.clone(), "v1", Vec::new());
+    assert!(cache.is_unchanged(&report, stamp, "v1"));
+    assert!(!cache.is_unchanged(&report, stamp, "v2"));
+
+    // Same contents under a new stamp: the stamp is brought up to date
+    touch(&report, 60);
+    let touched = FileStamp::of(&report).unwrap();
+    assert!(!cache.is_unchanged(&report, touched, "v1"));
+    assert!(matches!(cache.check_content(&report, touched, content_hash, "v1"), CacheCheck::Unchanged));
+    assert!(cache.is_unchanged(&report, touched, "v1"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cache_survives_a_restart_without_deleted_files() {
+    let dir = Path::new("test_cache_persist");
+    fs::create_dir_all(dir).unwrap();
+    let cache_path = dir.join("state/cache.json");
+    let kept = dir.join("kept.txt");
+    let deleted = dir.join("deleted.txt");
+    fs::write(&kept, "kept").unwrap();
+    fs::write(&deleted, "deleted").unwrap();
+
+    let mut cache = ScanCache::load(&cache_path);
+    for path in [&kept, &deleted] {
+        let stamp = FileStamp::of(path).unwrap();
+        let CacheCheck::Changed { content_hash } = cache.check(path, stamp, "v1").unwrap() else { panic!() };
+        cache.insert(path, stamp, content_hash, "v1", Vec::new());
+    }
+    assert_eq!(cache.dirty(), 2);
+    cache.save().unwrap();
+    assert_eq!(cache.dirty(), 0);
+    fs::remove_file(&deleted).unwrap();
+
+    let mut cache = ScanCache::load(&cache_path);
+    assert_eq!(cache.len(), 1);
+    assert!(cache.get(&deleted).is_none());
+    assert!(matches!(cache.check(&kept, FileStamp::of(&kept).unwrap(), "v1").unwrap(), CacheCheck::Unchanged));
+
+    fs::write(&cache_path, "not json").unwrap();
+    assert!(ScanCache::load(&cache_path).is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_changes_are_logged_until_the_log_outgrows_the_cache() {
+    let dir = Path::new("test_cache_log");
+    fs::create_dir_all(dir).unwrap();
+    let cache_path = dir.join("cache.json");
+    let log_path = dir.join("cache.json.log");
+    let files: Vec<_> = (0..3).map(|i| dir.join(format!("{}.txt", i))).collect();
+
+    let mut cache = ScanCache::load(&cache_path);
+    for (i, path) in files.iter().enumerate() {
+        fs::write(path, i.to_string()).unwrap();
+        cache.insert(path, FileStamp::of(path).unwrap(), i.to_string(), "v1", Vec::new());
+    }
+    cache.save().unwrap();
+    assert!(!cache_path.exists());
+    assert_eq!(fs::read_to_string(&log_path).unwrap().lines().count(), 3);
+
+    // A fourth logged change would outgrow the three entries
+    cache.insert(&files[0], FileStamp::of(&files[0]).unwrap(), "changed".to_string(), "v1", Vec::new());
+    cache.save().unwrap();
+    assert!(cache_path.exists());
+    assert!(!log_path.exists());
+
+    cache.insert(&files[1], FileStamp::of(&files[1]).unwrap(), "changed too".to_string(), "v1", Vec::new());
+    cache.save().unwrap();
+    assert_eq!(fs::read_to_string(&log_path).unwrap().lines().count(), 1);
+
+    let cache = ScanCache::load(&cache_path);
+    assert_eq!(cache.len(), 3);
+    assert_eq!(cache.get(&files[0]).unwrap().content_hash, "changed");
+    assert_eq!(cache.get(&files[1]).unwrap().content_hash, "changed too");
+    assert_eq!(cache.get(&files[2]).unwrap().content_hash, "2");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_rule_file_changes_the_detector_version() {
+    let dir = Path::new("test_cache_version");
+    fs::create_dir_all(dir).unwrap();
+    let rule_file = dir.join("rules.toml");
+    fs::write(&rule_file, "[[rules]]\nid = \"PROJECT_CODE\"\npattern = 'PRJ-\\d{4}'\nseverity = \"Low\"\n").unwrap();
+
+    let comm = Communication::new("test".to_string(), "http://127.0.0.1:9".to_string());
+    let monitor = FileMonitor::new(comm).with_rule_file(&rule_file).unwrap();
+    let version = monitor.detector_version();
+    assert_eq!(monitor.detector_version(), version);
+
+    fs::write(&rule_file, "[[rules]]\nid = \"PROJECT_CODE\"\npattern = 'PRJ-\\d{5}'\nseverity = \"Low\"\n").unwrap();
+    monitor.rules().unwrap().reload(&[]).unwrap();
+    assert_ne!(monitor.detector_version(), version);
+
+    let comm = Communication::new("test".to_string(), "http://127.0.0.1:9".to_string());
+    let strict = FileMonitor::new(comm).with_config(MonitorConfig { min_match_count: 2, ..Default::default() });
+    let comm = Communication::new("test".to_string(), "http://127.0.0.1:9".to_string());
+    let default_version = FileMonitor::new(comm).detector_version();
+    assert_ne!(strict.detector_version(), default_version);
+
+    // Settings that only decide which files are scanned, and when, keep it
+    let comm = Communication::new("test".to_string(), "http://127.0.0.1:9".to_string());
+    let tuned = FileMonitor::new(comm).with_config(MonitorConfig {
+        roots: vec![WatchRoot::new(dir)],
+        scope: ScopeConfig { max_file_size: Some(1024), ..Default::default() },
+        workers: WorkerConfig { max_workers: 16, timeout_secs: 30, ..Default::default() },
+        ..Default::default()
+    });
+    assert_eq!(tuned.detector_version(), default_version);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_unchanged_files_are_not_rescanned() {
+    let dir = Path::new("test_cache_monitor");
+    let watched = dir.join("watched");
+    fs::create_dir_all(&watched).unwrap();
+    let cache_path = dir.join("cache.json");
+
+    let comm = Communication::new("test".to_string(), common::mock_endpoint());
+    let alerts = comm.alerts.clone();
+    let monitor = FileMonitor::new(comm).with_config(MonitorConfig {
+        cache: CacheConfig { enabled: true, path: cache_path.clone(), flush_every: 100 },
+        ..Default::default()
+    });
+    let root = watched.clone();
+    let handle = tokio::spawn(async move {
+        monitor.start_monitoring(&root).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let notes = watched.join("notes.txt");
+    fs::write(&notes, "SSN: 123-45-6789").unwrap();
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    // Saved again with the same contents
+    fs::write(&notes, "SSN: 123-45-6789").unwrap();
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    fs::copy(&notes, watched.join("notes copy.txt")).unwrap();
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let ssn: Vec<String> = alerts
+        .lock()
+        .await
+        .iter()
+        .filter(|a| a.pattern_type == "US_SSN")
+        .map(|a| a.file_path.clone())
+        .collect();
+    assert_eq!(ssn.len(), 2, "{:?}", ssn);
+    assert!(ssn[0].ends_with("notes.txt"));
+    assert!(ssn[1].ends_with("notes copy.txt"));
+
+    let cache = ScanCache::load(&cache_path);
+    assert_eq!(cache.len(), 2);
+    let entry = cache.get(&fs::canonicalize(&notes).unwrap()).unwrap();
+    assert_eq!(entry.alerts.len(), 1);
+    assert_eq!(entry.alerts[0].pattern_type, "US_SSN");
+
+    handle.abort();
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,34 @@
+use file_monitor::detectors::builtin_detectors;
+use file_monitor::{Category, Confidence, Detector, KeywordRule, RegexDetector, Severity};
+
+#[test]
+fn test_supporting_keywords_raise_or_lower_confidence() {
+    let detectors = builtin_detectors();
+    let ssn = detectors.iter().find(|d| d.id() == "US_SSN").unwrap();
+
+    assert_eq!(ssn.detect("Employee SSN: 123-45-6789")[0].confidence, Confidence::High);
+    assert_eq!(ssn.detect("Social Security Number\n\t123-45-6789")[0].confidence, Confidence::High);
+    assert_eq!(ssn.detect("ticket 123-45-6789 closed")[0].confidence, Confidence::Low);
+}
+
+#[test]
+fn test_keyword_window_is_measured_in_characters() {
+    let rule = KeywordRule::new(&["ssn"], 10);
+    let content = "SSN ééééé 123-45-6789";
+    let start = content.find('1').unwrap();
+
+    assert!(rule.near(content, start, start + 11));
+    assert!(!KeywordRule::new(&["ssn"], 5).near(content, start, start + 11));
+    assert!(!KeywordRule::new(&["ssn"], 0).near(content, start, start + 11));
+}
+
+#[test]
+fn test_custom_detector_confidence() {
+    let detector = RegexDetector::new("EMPLOYEE_ID", "Employee ID", Category::Custom, Severity::Low, r"EMP-\d{4}")
+        .unwrap()
+        .with_confidence(Confidence::Low)
+        .with_keywords(KeywordRule::new(&["badge"], 20));
+
+    assert_eq!(detector.detect("badge EMP-1234")[0].confidence, Confidence::Medium);
+    assert_eq!(detector.detect("EMP-1234")[0].confidence, Confidence::Low);
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use file_monitor::debounce::is_temp_file;
+use file_monitor::{Communication, DebounceConfig, Debouncer, FileMonitor};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+#[test]
+fn test_temp_and_swap_files_are_recognised() {
+    for name in [".report.docx.swp", "notes.txt~", "~$budget.xlsx", ".~lock.deck.odp#", ".#main.rs", "#draft.txt#", "setup.exe.crdownload", "4913", "data.csv.tmp"] {
+        assert!(is_temp_file(Path::new(name)), "{}", name);
+    }
+    for name in ["report.docx", "budget.xlsx", "swp.txt", "temperature.csv"] {
+        assert!(!is_temp_file(Path::new(name)), "{}", name);
+    }
+
+    let mut debouncer = Debouncer::new(DebounceConfig::default());
+    assert!(!debouncer.event("dir/.notes.txt.swp".into(), Instant::now()));
+    assert_eq!(debouncer.pending(), 0);
+}
+
+#[test]
+fn test_bursts_are_coalesced_and_scanned_once_settled() {
+    let dir = Path::new("test_debounce_burst");
+    fs::create_dir_all(dir).unwrap();
+    let file = dir.join("report.txt");
+    fs::write(&file, "draft").unwrap();
+
+    let mut debouncer = Debouncer::new(DebounceConfig::default());
+    let start = Instant::now();
+    for i in 0..5 {
+        debouncer.event(file.clone(), start + ms(i * 100));
+    }
+    assert_eq!(debouncer.pending(), 1);
+    assert!(debouncer.ready(start + ms(800)).is_empty());
+
+    // Quiet period over: size and mtime are recorded, then must hold for the settle time
+    assert!(debouncer.ready(start + ms(900)).is_empty());
+    assert_eq!(debouncer.next_deadline(), Some(start + ms(1150)));
+    fs::write(&file, "draft, still being written").unwrap();
+    assert!(debouncer.ready(start + ms(1150)).is_empty());
+    assert_eq!(debouncer.ready(start + ms(1400)), vec![file.clone()]);
+    assert_eq!(debouncer.pending(), 0);
+    assert_eq!(debouncer.next_deadline(), None);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_busy_files_are_scanned_after_max_wait_and_missing_ones_dropped() {
+    let dir = Path::new("test_debounce_busy");
+    fs::create_dir_all(dir).unwrap();
+    let log = dir.join("app.log");
+    fs::write(&log, "line").unwrap();
+
+    let mut debouncer = Debouncer::new(DebounceConfig { max_wait_ms: 2000, ..Default::default() });
+    let start = Instant::now();
+    let mut now = start;
+    while now < start + ms(2000) {
+        debouncer.event(log.clone(), now);
+        assert!(debouncer.ready(now).is_empty());
+        now += ms(200);
+    }
+    assert_eq!(debouncer.ready(now), vec![log]);
+
+    debouncer.event(dir.join("deleted.txt"), start);
+    debouncer.event(dir.to_path_buf(), start);
+    assert!(debouncer.ready(start + ms(5000)).is_empty());
+    assert_eq!(debouncer.pending(), 0);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_repeated_saves_produce_one_alert() {
+    let dir = Path::new("test_debounce_monitor");
+    fs::create_dir_all(dir).unwrap();
+
+    let comm = Communication::new("test".to_string(), common::mock_endpoint());
+    let alerts = comm.alerts.clone();
+    let monitor = FileMonitor::new(comm);
+    let handle = tokio::spawn(async move {
+        monitor.start_monitoring(dir).await.unwrap();
+    });
+    tokio::time::sleep(ms(500)).await;
+
+    for i in 0..5 {
+        fs::write(dir.join(".notes.txt.swp"), "SSN: 123-45-6789").unwrap();
+        fs::write(dir.join("notes.txt"), format!("draft {}\nSSN: 123-45-6789", i)).unwrap();
+        tokio::time::sleep(ms(50)).await;
+    }
+    tokio::time::sleep(ms(2000)).await;
+
+    let alerts = alerts.lock().await;
+    let ssn: Vec<_> = alerts.iter().filter(|a| a.pattern_type == "US_SSN").collect();
+    assert_eq!(ssn.len(), 1, "{:?}", ssn.iter().map(|a| &a.file_path).collect::<Vec<_>>());
+    assert!(ssn[0].file_path.ends_with("notes.txt"));
+
+    handle.abort();
+    fs::remove_dir_all(dir).unwrap();
+}
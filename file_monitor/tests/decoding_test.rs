@@ -0,0 +1,73 @@
+mod common;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use file_monitor::decoding::decode_payloads;
+use file_monitor::{Communication, FileMonitor, PayloadEncoding};
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn test_base64_hex_and_url_payloads_are_decoded() {
+    let b64 = STANDARD.encode("password=hunter2");
+    let hex = hex::encode("SSN 123-45-6789");
+    let text = format!("token: {}\nblob={}\nnext=%2Fhome%3Fapi_key%3Dabc123\n", b64, hex);
+
+    let payloads = decode_payloads(&text, 3, 16);
+    let find = |chain: &[PayloadEncoding]| payloads.iter().find(|p| p.chain == chain).unwrap();
+
+    let decoded = find(&[PayloadEncoding::Base64]);
+    assert_eq!(decoded.text, "password=hunter2");
+    assert_eq!(&text[decoded.start..decoded.end], b64);
+    assert_eq!(find(&[PayloadEncoding::Hex]).text, "SSN 123-45-6789");
+    assert_eq!(find(&[PayloadEncoding::Url]).text, "next=/home?api_key=abc123");
+}
+
+#[test]
+fn test_nested_payloads_record_chain_and_respect_depth() {
+    let inner = hex::encode("password=hunter2");
+    let outer = STANDARD.encode(&inner);
+    let text = format!("config {}", outer);
+
+    let payloads = decode_payloads(&text, 3, 16);
+    let nested = payloads.iter().find(|p| p.text == "password=hunter2").unwrap();
+    assert_eq!(nested.chain, vec![PayloadEncoding::Base64, PayloadEncoding::Hex]);
+    assert_eq!(nested.start, 7);
+
+    let shallow = decode_payloads(&text, 1, 16);
+    assert!(shallow.iter().all(|p| p.text != "password=hunter2"));
+    assert!(decode_payloads(&text, 0, 16).is_empty());
+}
+
+#[test]
+fn test_identifiers_and_binary_blobs_are_ignored() {
+    let binary = STANDARD.encode([0u8, 159, 146, 150, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    let text = format!("let AbstractSingletonProxyFactoryBean = load(\"{}\");", binary);
+
+    assert!(decode_payloads(&text, 3, 16).is_empty());
+}
+
+#[tokio::test]
+async fn test_values_readable_in_a_blob_are_not_reported_again_as_decoded() {
+    let test_dir = Path::new("test_decoding_readable");
+    fs::create_dir_all(test_dir).unwrap();
+
+    let comm = Communication::new("test".to_string(), common::mock_endpoint());
+    let alerts = comm.alerts.clone();
+    let monitor = tokio::spawn(async move {
+        FileMonitor::new(comm).start_monitoring(test_dir).await.unwrap();
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+    let encoded = STANDARD.encode("SSN 234-56-7891");
+    fs::write(test_dir.join("notes.txt"), format!("SSN: 123-45-6789%0A\nbackup {}\n", encoded)).unwrap();
+    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+
+    let alerts = alerts.lock().await;
+    let ssn = alerts.iter().find(|alert| alert.pattern_type == "US_SSN").unwrap();
+    let chains: Vec<&[PayloadEncoding]> = ssn.locations.iter().map(|l| l.encoding_chain.as_slice()).collect();
+    assert_eq!(chains, vec![&[][..], &[PayloadEncoding::Base64][..]]);
+
+    monitor.abort();
+    fs::remove_dir_all(test_dir).unwrap();
+}
@@ -0,0 +1,54 @@
+use file_monitor::detectors::builtin_detectors;
+use file_monitor::{Category, Communication, Detector, FileMonitor, Finding, Severity};
+
+struct EmployeeIdDetector;
+
+impl Detector for EmployeeIdDetector {
+    fn id(&self) -> &str {
+        "EMPLOYEE_ID"
+    }
+
+    fn name(&self) -> &str {
+        "Employee ID"
+    }
+
+    fn category(&self) -> Category {
+        Category::Custom
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Medium
+    }
+
+    fn detect(&self, content: &str) -> Vec<Finding> {
+        content
+            .match_indices("EMP-")
+            .map(|(start, matched)| Finding::new(self.id(), matched, start, start + matched.len()))
+            .collect()
+    }
+}
+
+#[test]
+fn test_builtin_detectors_report_named_ids() {
+    let detectors = builtin_detectors();
+    let content = "Email: test@company.com\nSSN: 123-45-6789";
+
+    let ids: Vec<&str> = detectors
+        .iter()
+        .filter(|d| !d.detect(content).is_empty())
+        .map(|d| d.id())
+        .collect();
+
+    assert!(ids.contains(&"EMAIL_ADDRESS"));
+    assert!(ids.contains(&"US_SSN"));
+}
+
+#[test]
+fn test_register_custom_detector() {
+    let comm = Communication::new("test".to_string(), "http://127.0.0.1:9".to_string());
+    let mut monitor = FileMonitor::with_detectors(comm, Vec::new());
+    monitor.register_detector(Box::new(EmployeeIdDetector));
+
+    assert_eq!(monitor.detectors().len(), 1);
+    assert_eq!(monitor.detectors()[0].detect("badge EMP-1234")[0].start, 6);
+}
@@ -0,0 +1,67 @@
+use file_monitor::ContentScanner;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use zip::write::FileOptions;
+
+fn write_archive(path: &Path, parts: &[(&str, &str)]) {
+    let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+    for (name, content) in parts {
+        zip.start_file(*name, FileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[tokio::test]
+async fn test_docx_body_header_comments_and_tracked_changes() {
+    let dir = Path::new("test_docx");
+    fs::create_dir_all(dir).unwrap();
+    let path = dir.join("report.docx");
+    write_archive(&path, &[
+        ("word/document.xml", r#"<w:document xmlns:w="w"><w:body>
+            <w:p><w:r><w:t>SSN: 123-45-</w:t></w:r><w:r><w:t>6789</w:t></w:r></w:p>
+            <w:p><w:del><w:r><w:delText>old card 4111</w:delText></w:r></w:del></w:p>
+        </w:body></w:document>"#),
+        ("word/header1.xml", r#"<w:hdr xmlns:w="w"><w:p><w:r><w:t>CONFIDENTIAL</w:t></w:r></w:p></w:hdr>"#),
+        ("word/comments.xml", r#"<w:comments xmlns:w="w"><w:comment><w:p><w:r><w:t>call hr@company.com</w:t></w:r></w:p></w:comment></w:comments>"#),
+        ("word/styles.xml", r#"<w:styles xmlns:w="w"><w:style><w:name w:val="Normal"/></w:style></w:styles>"#),
+        ("word/media/image1.png", "\u{89}PNG binary"),
+    ]);
+
+    let contents = ContentScanner::new().scan(&path).await.unwrap().contents;
+    let text: Vec<&str> = contents.iter().map(|c| c.text.as_str()).collect();
+    let joined = text.join("\n");
+
+    assert!(joined.contains("SSN: 123-45-6789"));
+    assert!(joined.contains("old card 4111"));
+    assert!(joined.contains("CONFIDENTIAL"));
+    assert!(joined.contains("hr@company.com"));
+    assert!(!joined.contains("Normal"));
+    assert_eq!(contents[0].origin.entry.as_deref(), Some("word/comments.xml"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_odp_speaker_notes() {
+    let dir = Path::new("test_odp");
+    fs::create_dir_all(dir).unwrap();
+    let path = dir.join("deck.odp");
+    write_archive(&path, &[
+        ("mimetype", "application/vnd.oasis.opendocument.presentation"),
+        ("content.xml", r#"<office:document-content xmlns:office="o" xmlns:text="t" xmlns:presentation="p">
+            <office:body><office:presentation><draw:page xmlns:draw="d">
+                <text:p>Quarterly<text:s/>results</text:p>
+                <presentation:notes><text:p>Remind finance: password=hunter2</text:p></presentation:notes>
+            </draw:page></office:presentation></office:body></office:document-content>"#),
+    ]);
+
+    let contents = ContentScanner::new().scan(&path).await.unwrap().contents;
+
+    assert_eq!(contents.len(), 1);
+    assert!(contents[0].text.contains("Quarterly results"));
+    assert!(contents[0].text.contains("password=hunter2"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,82 @@
+use file_monitor::EdmIndex;
+
+const RECORDS: &str = "\
+name,email,account
+Jane Doe,jane.doe@example.com,4021-7788-19
+Raj Patel,raj@example.org,5512-0093-44
+";
+
+fn index(salt: &str) -> EdmIndex {
+    EdmIndex::build(RECORDS.as_bytes(), salt, 2).unwrap()
+}
+
+#[test]
+fn test_index_stores_only_hashes() {
+    let index = index("pepper");
+    assert_eq!(index.fields, vec!["name", "email", "account"]);
+    assert_eq!(index.record_count(), 2);
+
+    let json = serde_json::to_string(&index).unwrap();
+    assert!(!json.contains("jane"));
+    assert!(!json.contains("4021-7788-19"));
+}
+
+#[test]
+fn test_values_are_found_case_and_whitespace_insensitively() {
+    let index = index("pepper");
+    let text = "Customer: JANE   doe <Jane.Doe@example.com>";
+    let hits = index.find(text);
+
+    let found: Vec<&str> = hits.iter().map(|h| &text[h.start..h.end]).collect();
+    assert_eq!(found, vec!["JANE   doe", "Jane.Doe@example.com"]);
+    assert!(hits.iter().all(|h| h.record == 0));
+}
+
+#[test]
+fn test_records_need_min_fields_to_co_occur() {
+    let index = index("pepper");
+
+    let one_field = index.find("Jane Doe called about her account");
+    assert!(index.co_occurring(&one_field).is_empty());
+
+    // Fields from different records do not add up
+    let mixed = index.find("Jane Doe, raj@example.org");
+    assert!(index.co_occurring(&mixed).is_empty());
+
+    let matched = index.co_occurring(&index.find("acct 4021-7788-19 owner jane.doe@example.com"));
+    assert_eq!(matched.len(), 1);
+    assert_eq!(matched[0].record, 0);
+    assert_eq!(matched[0].fields.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+}
+
+#[test]
+fn test_salt_and_round_trip() {
+    let dir = std::env::temp_dir().join("edm_test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("index.json");
+
+    index("pepper").save(&path).unwrap();
+    let loaded = EdmIndex::load(&path).unwrap();
+    assert_eq!(loaded.find("Raj Patel raj@example.org").len(), 2);
+    assert_eq!(loaded.digest(), index("pepper").digest());
+    assert_ne!(loaded.digest(), index("salt").digest());
+
+    let other = serde_json::to_string(&index("salt")).unwrap();
+    assert_ne!(std::fs::read_to_string(&path).unwrap(), other);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_values_shared_by_many_records_are_ignored() {
+    let mut csv = String::from("name,city\n");
+    for i in 0..50 {
+        csv.push_str(&format!("Person{},Springfield\n", i));
+    }
+    let index = EdmIndex::build(csv.as_bytes(), "pepper", 2).unwrap();
+
+    assert!(index.find("Springfield").is_empty());
+    let hits = index.find("Person7 from Springfield");
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].record, 7);
+}
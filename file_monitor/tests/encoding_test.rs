@@ -0,0 +1,56 @@
+use encoding_rs::{SHIFT_JIS, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use file_monitor::ContentScanner;
+use std::fs;
+use std::path::Path;
+
+async fn scan_bytes(dir: &Path, name: &str, bytes: &[u8]) -> String {
+    fs::write(dir.join(name), bytes).unwrap();
+    let output = ContentScanner::new().scan(&dir.join(name)).await.unwrap();
+    output.contents.iter().map(|c| c.text.as_str()).collect()
+}
+
+fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+    text.encode_utf16()
+        .flat_map(|unit| if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_text_is_transcoded_before_scanning() {
+    let dir = Path::new("test_encoding_text");
+    fs::create_dir_all(dir).unwrap();
+    let text = "Kundennummer für Jürgen: SSN 123-45-6789\r\nE-Mail: jürgen@example.com\r\n";
+
+    let mut with_bom = vec![0xff, 0xfe];
+    with_bom.extend(utf16(text, false));
+    assert_eq!(scan_bytes(dir, "export_bom.txt", &with_bom).await, text);
+    assert_eq!(scan_bytes(dir, "export_le.txt", &utf16(text, false)).await, text);
+    assert_eq!(scan_bytes(dir, "export_be.txt", &utf16(text, true)).await, text);
+
+    let (latin1, _, _) = WINDOWS_1252.encode(text);
+    assert_eq!(scan_bytes(dir, "latin1.txt", &latin1).await, text);
+
+    let japanese = "顧客の電話番号とパスワードを記録します。\npassword=hunter2 メール: tanaka@example.jp\n";
+    let (shift_jis, _, _) = SHIFT_JIS.encode(japanese);
+    assert_eq!(scan_bytes(dir, "sjis.txt", &shift_jis).await, japanese);
+
+    // Sanity check that the fixtures really are in the encodings under test
+    assert_eq!(UTF_16LE.decode_without_bom_handling(&utf16(text, false)).0, text);
+    assert_eq!(UTF_16BE.decode_without_bom_handling(&utf16(text, true)).0, text);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_binary_files_fall_back_to_printable_strings() {
+    let dir = Path::new("test_encoding_binary");
+    fs::create_dir_all(dir).unwrap();
+    let mut binary = vec![0x00, 0x01, 0x02, 0x03, 0xff, 0x10, 0x00, 0x11];
+    binary.extend_from_slice(b"api_key=abcdef123456");
+    binary.extend_from_slice(&[0x00, 0x02, b'a', b'b', 0x03, 0x00, 0x7f, 0x01, 0x02, 0x05]);
+
+    let text = scan_bytes(dir, "blob.bin", &binary).await;
+    assert_eq!(text, "api_key=abcdef123456\n");
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,76 @@
+use file_monitor::entropy::shannon_entropy;
+mod common;
+
+use file_monitor::{Communication, Confidence, Detector, EntropyDetector, EntropyThresholds, FileMonitor};
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn test_shannon_entropy() {
+    assert_eq!(shannon_entropy("aaaa"), 0.0);
+    assert_eq!(shannon_entropy("abcd"), 2.0);
+}
+
+#[test]
+fn test_high_entropy_tokens_are_low_confidence_findings() {
+    let detector = EntropyDetector::default();
+    let content = concat!(
+        "db_token: 'q8Zr2VxL0pN7yT4kWm1sB9dF6gH3jK5c'\n",
+        "# ----------------------------------------------------------------\n",
+        "value = 9f86d081884c7d659a2feaa0c55ad015\n",
+    );
+
+    let findings = detector.detect(content);
+    let matched: Vec<&str> = findings.iter().map(|f| f.matched.as_str()).collect();
+
+    assert_eq!(matched, vec!["q8Zr2VxL0pN7yT4kWm1sB9dF6gH3jK5c", "9f86d081884c7d659a2feaa0c55ad015"]);
+    assert_eq!(findings[0].confidence, Confidence::Medium);
+    assert_eq!(findings[1].confidence, Confidence::Low);
+    assert!(findings[0].metadata["entropy"].parse::<f64>().unwrap() >= 4.5);
+    assert_eq!(&content[findings[1].start..findings[1].end], "9f86d081884c7d659a2feaa0c55ad015");
+}
+
+#[test]
+fn test_known_false_positives_are_suppressed() {
+    let detector = EntropyDetector::default();
+    let content = concat!(
+        "id = 3f2b8c1e-9a4d-4e7b-8c2f-1d5a6b7c8d9e\n",
+        "checksum = \"b2e6cc8c0f4a3c1a9e6b0a5fd0a1c7e3d9b4f2a6e8c0d1b3a5f7e9c1d3b5a7f9\"\n",
+        "  integrity sha512-q8Zr2VxL0pN7yT4kWm1sB9dF6gH3jK5cq8Zr2VxL0pN7yT4kWm1sB9dF6gH3jK5c==\n",
+        "github.com/pkg/errors v0.9.1 h1:FEBLx1zS214owpjy7qsBeixbURkuhQAwrK5UwLGTwt4=\n",
+        "const AbstractSingletonProxyFactoryBeanName = 1\n",
+    );
+
+    assert!(detector.detect(content).is_empty());
+}
+
+#[test]
+fn test_thresholds_are_configurable() {
+    let strict = EntropyDetector::new(EntropyThresholds {
+        base64_bits: 5.5,
+        ..Default::default()
+    });
+    assert!(strict.detect("token q8Zr2VxL0pN7yT4kWm1sB9dF6gH3jK5c").is_empty());
+}
+
+#[tokio::test]
+async fn test_tokens_known_to_other_detectors_are_reported_once() {
+    let test_dir = Path::new("test_entropy_overlap");
+    fs::create_dir_all(test_dir).unwrap();
+
+    let comm = Communication::new("test".to_string(), common::mock_endpoint());
+    let alerts = comm.alerts.clone();
+    let monitor = tokio::spawn(async move {
+        FileMonitor::new(comm).start_monitoring(test_dir).await.unwrap();
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+    fs::write(test_dir.join("aws.env"), "aws_secret_access_key = \"wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY\"\n").unwrap();
+    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+
+    let types: Vec<String> = alerts.lock().await.iter().map(|alert| alert.pattern_type.clone()).collect();
+    assert_eq!(types, vec!["AWS_SECRET_ACCESS_KEY"]);
+
+    monitor.abort();
+    fs::remove_dir_all(test_dir).unwrap();
+}
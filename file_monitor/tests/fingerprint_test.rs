@@ -0,0 +1,93 @@
+use file_monitor::config::{ScannerConfig, TextLimits};
+use file_monitor::fingerprint::Shingler;
+use file_monitor::{ContentScanner, FingerprintIndex};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+const CONTRACT: &str = "This Master Services Agreement is entered into by Acme Corporation and Globex Limited. \
+The supplier shall deliver the services described in each statement of work with reasonable skill and care. \
+Fees are payable within thirty days of invoice and late payments accrue interest at four percent per annum. \
+Either party may terminate this agreement on ninety days written notice to the other party. \
+All confidential information disclosed under this agreement remains the property of the disclosing party.";
+
+fn shingles(index: &FingerprintIndex, text: &str) -> HashSet<u64> {
+    let mut shingler = index.shingler();
+    shingler.feed(text, true);
+    shingler.take().into_iter().collect()
+}
+
+#[test]
+fn test_rolling_hash_ignores_how_text_is_split() {
+    let mut whole = Shingler::new(4);
+    whole.feed("The quick brown fox jumps over the lazy dog", true);
+
+    let mut pieces = Shingler::new(4);
+    pieces.feed("The qu", false);
+    pieces.feed("ick BROWN fox, jumps", false);
+    pieces.feed("\nover the la", false);
+    pieces.feed("zy dog", false);
+    pieces.finish();
+
+    let whole = whole.take();
+    assert_eq!(whole.len(), 6);
+    assert_eq!(whole, pieces.take());
+}
+
+#[test]
+fn test_partial_copies_match_above_threshold() {
+    let mut index = FingerprintIndex::new(5, 0.3);
+    index.register_text("msa.docx", CONTRACT);
+    index.register_text("roadmap.pdf", "Project Falcon launches in the third quarter with a new pricing tier for enterprise customers");
+
+    // Two of the five sentences pasted into an email, with different casing and layout
+    let email = "Hi team, see below:\n\nFEES ARE PAYABLE within thirty days of invoice and late payments accrue \
+interest at four percent per annum.\n> Either party may terminate this agreement on ninety days written notice to the other party.";
+    let matches = index.matches(&shingles(&index, email));
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].document, "msa.docx");
+    assert!(matches[0].similarity > 0.3 && matches[0].similarity < 1.0);
+
+    let unrelated = "The supplier shall deliver lunch on Friday. Fees are payable at the door.";
+    assert!(index.matches(&shingles(&index, unrelated)).is_empty());
+
+    let whole = index.matches(&shingles(&index, CONTRACT));
+    assert_eq!(whole[0].similarity, 1.0);
+}
+
+#[test]
+fn test_digest_follows_registrations() {
+    let mut index = FingerprintIndex::new(5, 0.3);
+    let empty = index.digest().to_string();
+    index.register_text("msa.docx", CONTRACT);
+    assert_ne!(index.digest(), empty);
+
+    let mut same = FingerprintIndex::new(5, 0.3);
+    same.register_text("msa.docx", CONTRACT);
+    assert_eq!(same.digest(), index.digest());
+}
+
+#[tokio::test]
+async fn test_files_are_fingerprinted_through_the_scanner() {
+    let dir = Path::new("test_fingerprint_files");
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("msa.txt"), CONTRACT).unwrap();
+
+    // Small chunks split words, which must not change the fingerprint
+    let scanner = ContentScanner::with_config(ScannerConfig {
+        text: TextLimits {
+            chunk_bytes: 37,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let mut index = FingerprintIndex::default();
+    index.register_file(&scanner, &dir.join("msa.txt")).await.unwrap();
+    assert_eq!(index.documents()[0].shingle_count, shingles(&index, CONTRACT).len());
+
+    index.save(&dir.join("index.json")).unwrap();
+    let loaded = FingerprintIndex::load(&dir.join("index.json")).unwrap();
+    assert_eq!(loaded.matches(&shingles(&loaded, CONTRACT))[0].similarity, 1.0);
+
+    fs::remove_dir_all(dir).unwrap();
+}
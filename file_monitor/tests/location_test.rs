@@ -0,0 +1,42 @@
+use file_monitor::{ContentOrigin, Finding, MatchLocation, ScannedContent};
+
+#[test]
+fn test_locate_reports_line_column_and_redacted_context() {
+    let text = "Employee Information\nSSN: 123-45-6789 on file\n";
+    let content = ScannedContent::new(ContentOrigin::default(), text.to_string());
+    let start = text.find("123").unwrap();
+    let finding = Finding::new("US_SSN", "123-45-6789", start, start + 11);
+
+    let location = MatchLocation::locate(&content, &finding, 5);
+
+    assert_eq!(location.offset, start);
+    assert_eq!(location.line, 2);
+    assert_eq!(location.column, 6);
+    assert_eq!(location.context, "***: [US_SSN] ** *");
+}
+
+#[test]
+fn test_context_does_not_leak_neighbouring_values() {
+    let text = "123-45-6789,4111111111111111";
+    let content = ScannedContent::new(ContentOrigin::default(), text.to_string());
+    let finding = Finding::new("US_SSN", "123-45-6789", 0, 11);
+
+    let location = MatchLocation::locate(&content, &finding, 20);
+
+    assert_eq!(location.context, "[US_SSN],****************");
+}
+
+#[test]
+fn test_locate_keeps_origin() {
+    let origin = ContentOrigin {
+        page: Some(3),
+        ..Default::default()
+    };
+    let content = ScannedContent::new(origin.clone(), "card 4111 1111 1111 1111".to_string());
+    let finding = Finding::new("CREDIT_CARD_NUMBER", "4111 1111 1111 1111", 5, 24);
+
+    let location = MatchLocation::locate(&content, &finding, 0);
+
+    assert_eq!(location.origin, origin);
+    assert_eq!(location.context, "[CREDIT_CARD_NUMBER]");
+}
@@ -0,0 +1,52 @@
+use file_monitor::{MonitorConfig, RedactionConfig, RedactionMode};
+use std::fs;
+use std::path::Path;
+
+fn redaction(mode: RedactionMode) -> RedactionConfig {
+    RedactionConfig { mode, salt: "pepper".to_string() }
+}
+
+fn redact(mode: RedactionMode, value: &str) -> String {
+    redaction(mode).redact(value)
+}
+
+#[test]
+fn test_redaction_modes() {
+    assert_eq!(redact(RedactionMode::FullMask, "123-45-6789"), "********");
+    assert_eq!(redact(RedactionMode::KeepLast4, "4111 1111 1111 1111"), "**** **** **** 1111");
+    assert_eq!(redact(RedactionMode::KeepLast4, "abc"), "***");
+    assert_eq!(redact(RedactionMode::FormatPreserving, "123-45-6789"), "***-**-****");
+}
+
+#[test]
+fn test_salted_hash_is_stable_and_salt_dependent() {
+    let token = redact(RedactionMode::SaltedHash, "123-45-6789");
+    assert!(token.starts_with("sha256:"));
+    assert!(!token.contains("6789"));
+    assert_eq!(token, redact(RedactionMode::SaltedHash, "123-45-6789"));
+
+    let unsalted = RedactionConfig { mode: RedactionMode::SaltedHash, salt: String::new() };
+    assert_ne!(token, unsalted.redact("123-45-6789"));
+}
+
+#[test]
+fn test_salted_hash_requires_a_salt() {
+    let unsalted = RedactionConfig { mode: RedactionMode::SaltedHash, salt: String::new() };
+    assert!(unsalted.validate().is_err());
+    assert_eq!(unsalted.redact("123-45-6789"), "********");
+    // Alerts record the masking actually applied
+    assert_eq!(unsalted.applied_mode(), RedactionMode::FullMask);
+    assert_eq!(redaction(RedactionMode::SaltedHash).applied_mode(), RedactionMode::SaltedHash);
+    assert!(RedactionConfig { mode: RedactionMode::SaltedHash, salt: "pepper".to_string() }.validate().is_ok());
+    assert!(RedactionConfig::default().validate().is_ok());
+
+    let dir = Path::new("test_redaction_salt");
+    fs::create_dir_all(dir).unwrap();
+    let path = dir.join("monitor.toml");
+    fs::write(&path, "[redaction]\nmode = \"SaltedHash\"\n").unwrap();
+    let error = MonitorConfig::load(&path).unwrap_err();
+    assert!(error.to_string().contains("redaction.salt"), "{}", error);
+    fs::write(&path, "[redaction]\nmode = \"SaltedHash\"\nsalt = \"pepper\"\n").unwrap();
+    assert!(MonitorConfig::load(&path).is_ok());
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,79 @@
+use file_monitor::config::MonitorConfig;
+use file_monitor::regional::{eu_detectors, india_detectors, passport_detectors, uk_detectors};
+use file_monitor::validators::{aadhaar_valid, de_id_card_valid, es_dni_valid, eu_iban_valid, fr_nir_valid, gstin_valid, nino_valid};
+use file_monitor::{Communication, Detector, DetectorPack, FileMonitor};
+
+fn ids(detectors: &[Box<dyn Detector>], content: &str) -> Vec<String> {
+    detectors
+        .iter()
+        .flat_map(|d| d.detect(content))
+        .map(|f| f.detector_id)
+        .collect()
+}
+
+#[test]
+fn test_regional_checksums() {
+    assert!(aadhaar_valid("2345 6789 0124"));
+    assert!(!aadhaar_valid("2345 6789 0125"));
+    assert!(!aadhaar_valid("1345 6789 0124"));
+    assert!(gstin_valid("27AAPFU0939F1ZV"));
+    assert!(!gstin_valid("27AAPFU0939F1ZW"));
+    assert!(gstin_valid("97AAPFU0939F1ZO"));
+    // Check characters are right, state codes are not
+    assert!(!gstin_valid("39AAPFU0939F1ZQ"));
+    assert!(!gstin_valid("1AAAPFU0939F1ZQ"));
+    assert!(nino_valid("AB 12 34 56 C"));
+    assert!(!nino_valid("GB123456A"));
+    assert!(de_id_card_valid("T220001293"));
+    assert!(!de_id_card_valid("T220001294"));
+    assert!(fr_nir_valid("1 84 12 76 451 089 46"));
+    assert!(!fr_nir_valid("1 84 12 76 451 089 47"));
+    assert!(fr_nir_valid("2 55 08 2A 004 005 90"));
+    // Keyed as if the "2A" straddling birth month and département were Corsica
+    assert!(!fr_nir_valid("1 84 12 A0 451 089 40"));
+    assert!(es_dni_valid("12345678Z"));
+    assert!(es_dni_valid("X-1234567-L"));
+    assert!(!es_dni_valid("12345678A"));
+    assert!(eu_iban_valid("DE89 3704 0044 0532 0130 00"));
+    assert!(!eu_iban_valid("GB82 WEST 1234 5698 7654 32"));
+}
+
+#[test]
+fn test_regional_packs_detect_valid_identifiers() {
+    let india = india_detectors();
+    assert_eq!(ids(&india, "Aadhaar: 2345 6789 0124"), vec!["AADHAAR"]);
+    assert_eq!(ids(&india, "PAN AAPFU0939F"), vec!["IN_PAN"]);
+    assert!(ids(&india, "GSTIN 27AAPFU0939F1ZV").contains(&"IN_GSTIN".to_string()));
+    assert!(ids(&india, "ref 2345 6789 0125").is_empty());
+
+    assert_eq!(ids(&uk_detectors(), "NI number AB 12 34 56 C"), vec!["UK_NATIONAL_INSURANCE"]);
+
+    let eu = eu_detectors();
+    assert_eq!(ids(&eu, "IBAN DE89 3704 0044 0532 0130 00"), vec!["EU_IBAN"]);
+    assert!(ids(&eu, "IBAN GB82 WEST 1234 5698 7654 32").is_empty());
+    assert_eq!(ids(&eu, "Personalausweis T220001293"), vec!["DE_ID_CARD"]);
+    assert_eq!(ids(&eu, "NIR 1 84 12 76 451 089 46"), vec!["FR_NIR"]);
+    assert_eq!(ids(&eu, "DNI 12345678Z, NIE X1234567L"), vec!["ES_DNI", "ES_DNI"]);
+}
+
+#[test]
+fn test_passports_require_a_keyword() {
+    let passports = passport_detectors();
+    assert_eq!(ids(&passports, "Passport No: J8369854"), vec!["IN_PASSPORT"]);
+    assert!(ids(&passports, "Invoice J8369854 and order 123456789").is_empty());
+}
+
+#[test]
+fn test_packs_are_selected_per_policy_group() {
+    let mut config = MonitorConfig::default();
+    config.policy_groups.insert("finance-dept".to_string(), vec![DetectorPack::India, DetectorPack::Eu]);
+    let comm = || Communication::new("test".to_string(), "http://127.0.0.1:9".to_string());
+
+    let finance = FileMonitor::new(comm()).with_config(config.clone()).with_policy_group("finance-dept");
+    let other = FileMonitor::new(comm()).with_config(config).with_policy_group("engineering");
+    let has = |monitor: &FileMonitor, id: &str| monitor.detectors().iter().any(|d| d.id() == id);
+
+    assert!(has(&finance, "AADHAAR") && has(&finance, "EU_IBAN"));
+    assert!(!has(&finance, "UK_NATIONAL_INSURANCE"));
+    assert!(!has(&other, "AADHAAR"));
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use file_monitor::rules::{load_rules, parse_rules, RuleFormat};
+use file_monitor::{Communication, ContentOrigin, FileMonitor, RuleSet, Severity};
+use std::fs;
+use std::path::Path;
+
+const TOML_RULES: &str = r#"
+[[rules]]
+id = "EMPLOYEE_ID"
+name = "Employee ID"
+pattern = 'EMP-\d{6}'
+severity = "Medium"
+keywords = ["employee", "staff"]
+
+[[rules]]
+id = "PROJECT_CODENAME"
+pattern = '(?i)\bproject (falcon|osprey)\b'
+severity = "High"
+category = "Confidential"
+file_types = ["docx", "pdf"]
+"#;
+
+const YAML_RULES: &str = r#"
+rules:
+  - id: CORPORATE_CARD
+    pattern: '\b4\d{15}\b'
+    severity: Critical
+    category: Financial
+    validator: Luhn
+"#;
+
+#[test]
+fn test_rules_load_from_toml_and_yaml() {
+    let toml = parse_rules(TOML_RULES, RuleFormat::Toml).unwrap();
+    assert_eq!(toml.len(), 2);
+    assert_eq!(toml[0].name(), "Employee ID");
+    assert_eq!(toml[1].name(), "PROJECT_CODENAME");
+    assert_eq!(toml[1].severity(), Severity::High);
+
+    let findings = toml[0].detect("staff member EMP-004211");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].detector_id, "EMPLOYEE_ID");
+
+    let yaml = parse_rules(YAML_RULES, RuleFormat::Yaml).unwrap();
+    assert_eq!(yaml[0].detect("card 4111111111111111").len(), 1);
+    assert!(yaml[0].detect("card 4111111111111112").is_empty());
+}
+
+#[test]
+fn test_validators_reject_non_ascii_matches() {
+    // Any validator can be attached to any pattern, so they must cope with
+    // text their built-in detectors would never hand them
+    let rules = parse_rules(
+        r#"
+[[rules]]
+id = "LOOSE_NINO"
+pattern = '\w{8,9}'
+severity = "High"
+validator = "UkNino"
+
+[[rules]]
+id = "LOOSE_NIR"
+pattern = '\w{14,15}'
+severity = "High"
+validator = "FrNir"
+"#,
+        RuleFormat::Toml,
+    )
+    .unwrap();
+    assert!(rules[0].detect("aé123456").is_empty());
+    assert!(rules[1].detect("é1234567890123").is_empty());
+    assert_eq!(rules[0].detect("AB123456C").len(), 1);
+}
+
+#[test]
+fn test_file_type_scope() {
+    let rules = parse_rules(TOML_RULES, RuleFormat::Toml).unwrap();
+    let origin = ContentOrigin::default();
+    let in_zip = ContentOrigin {
+        entry: Some("plans/roadmap.docx/word/document.xml".to_string()),
+        ..Default::default()
+    };
+
+    assert!(rules[1].applies_to(Path::new("/tmp/Deck.PDF"), &origin));
+    assert!(rules[1].applies_to(Path::new("/tmp/export.zip"), &in_zip));
+    assert!(!rules[1].applies_to(Path::new("/tmp/notes.txt"), &origin));
+    assert!(rules[0].applies_to(Path::new("/tmp/notes.txt"), &origin));
+}
+
+#[test]
+fn test_invalid_rules_are_all_reported() {
+    let text = r#"
+[[rules]]
+id = "BAD_REGEX"
+pattern = 'EMP-(\d+'
+severity = "Low"
+
+[[rules]]
+id = "lower_case"
+pattern = 'x+'
+severity = "Low"
+
+[[rules]]
+id = "EMPTY_MATCH"
+pattern = '\d*'
+severity = "Low"
+
+[[rules]]
+id = "BAD_REGEX"
+pattern = 'y'
+severity = "Low"
+"#;
+    let error = parse_rules(text, RuleFormat::Toml).err().unwrap().to_string();
+    // Regex errors span several lines to point at the problem
+    let lines: Vec<&str> = error.lines().filter(|line| line.starts_with("rule ")).collect();
+    assert_eq!(lines.len(), 4, "{}", error);
+    assert!(lines[0].starts_with("rule 1 (BAD_REGEX): invalid pattern"));
+    assert!(lines[1].contains("upper case"));
+    assert!(lines[2].contains("matches the empty string"));
+    assert_eq!(lines[3], "rule 4 (BAD_REGEX): duplicate id");
+
+    let unknown = parse_rules("rules:\n  - id: X\n    pattern: x\n    severity: Low\n    sevrity: High\n", RuleFormat::Yaml);
+    assert!(unknown.err().unwrap().to_string().contains("sevrity"));
+    let bad_severity = parse_rules("[[rules]]\nid = \"X\"\npattern = \"x\"\nseverity = \"Severe\"\n", RuleFormat::Toml);
+    assert!(bad_severity.err().unwrap().to_string().contains("Severe"));
+
+    let error = load_rules(Path::new("rules.json"), &[]).err().unwrap().to_string();
+    assert!(error.contains(".toml, .yaml or .yml"));
+}
+
+#[test]
+fn test_reload_keeps_previous_rules_on_error() {
+    let dir = Path::new("test_rules_reload");
+    fs::create_dir_all(dir).unwrap();
+    let path = dir.join("rules.yaml");
+    fs::write(&path, YAML_RULES).unwrap();
+
+    let rules = RuleSet::load(&path, &[]).unwrap();
+    assert_eq!(rules.detectors()[0].id(), "CORPORATE_CARD");
+
+    fs::write(&path, "rules:\n  - id: BROKEN\n    pattern: '('\n    severity: Low\n").unwrap();
+    assert!(rules.reload(&[]).is_err());
+    assert_eq!(rules.detectors()[0].id(), "CORPORATE_CARD");
+
+    fs::write(&path, "rules:\n  - id: TICKET\n    pattern: 'TCK-\\d+'\n    severity: Low\n").unwrap();
+    assert_eq!(rules.reload(&[]).unwrap(), 1);
+    assert_eq!(rules.detectors()[0].id(), "TICKET");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_rule_ids_must_not_clash_with_other_detectors() {
+    let dir = Path::new("test_rules_clash");
+    fs::create_dir_all(dir).unwrap();
+    let path = dir.join("rules.yaml");
+    fs::write(&path, "rules:\n  - id: TICKET\n    pattern: 'TCK-\\d+'\n    severity: Low\n").unwrap();
+
+    let comm = || Communication::new("test".to_string(), "http://127.0.0.1:9".to_string());
+    let monitor = FileMonitor::new(comm()).with_rule_file(&path).unwrap();
+
+    let shadowing = "rules:\n  - id: TICKET\n    pattern: 'TCK-\\d+'\n    severity: Low\n\n  - id: US_SSN\n    pattern: '\\d{9}'\n    severity: High\n";
+    fs::write(&path, shadowing).unwrap();
+    let error = FileMonitor::new(comm()).with_rule_file(&path).err().unwrap().to_string();
+    assert_eq!(error, format!("{}:6: rule 2 (US_SSN): id is already used by another detector", path.display()));
+    // A failed reload keeps the rules already loaded
+    assert!(monitor.rules().unwrap().reload(&["US_SSN"]).is_err());
+    assert_eq!(monitor.rules().unwrap().detectors()[0].id(), "TICKET");
+
+    let toml = dir.join("rules.toml");
+    fs::write(&toml, "[[rules]]\nid = \"TICKET\"\npattern = 'a+'\nseverity = \"Low\"\n\n[[rules]]\nid = \"TICKET\"\npattern = 'b+'\nseverity = \"Low\"\n").unwrap();
+    let error = load_rules(&toml, &[]).err().unwrap().to_string();
+    assert_eq!(error, format!("{}:7: rule 2 (TICKET): duplicate id", toml.display()));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_rule_file_is_hot_reloaded_while_monitoring() {
+    let dir = Path::new("test_rules_hot_reload");
+    let watched = dir.join("watched");
+    fs::create_dir_all(&watched).unwrap();
+    let rule_file = dir.join("rules.toml");
+    fs::write(&rule_file, "[[rules]]\nid = \"TICKET_OLD\"\npattern = 'TCK-\\d+'\nseverity = \"Low\"\n").unwrap();
+
+    let comm = Communication::new("test".to_string(), common::mock_endpoint());
+    let alerts = comm.alerts.clone();
+    let monitor = FileMonitor::with_detectors(comm, Vec::new()).with_rule_file(&rule_file).unwrap();
+    let handle = tokio::spawn(async move {
+        monitor.start_monitoring(&watched).await.unwrap();
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+    fs::write(&rule_file, "[[rules]]\nid = \"TICKET_NEW\"\npattern = 'TCK-\\d+'\nseverity = \"Low\"\n").unwrap();
+    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    fs::write(dir.join("watched/ticket.txt"), "see TCK-1042").unwrap();
+    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+
+    let ids: Vec<String> = alerts.lock().await.iter().map(|a| a.pattern_type.clone()).collect();
+    assert!(ids.contains(&"TICKET_NEW".to_string()), "{:?}", ids);
+    assert!(!ids.contains(&"TICKET_OLD".to_string()));
+
+    handle.abort();
+    fs::remove_dir_all(dir).unwrap();
+}
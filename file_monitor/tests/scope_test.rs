@@ -0,0 +1,156 @@
+mod common;
+
+use file_monitor::config::MonitorConfig;
+use file_monitor::{BaselineConfig, BaselineCrawl, Communication, FileMonitor, FileScope, ScopeConfig, WatchRoot};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+fn root(path: &Path, include: &[&str], exclude: &[&str]) -> WatchRoot {
+    WatchRoot {
+        path: path.to_path_buf(),
+        include: include.iter().map(|p| p.to_string()).collect(),
+        exclude: exclude.iter().map(|p| p.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_globs_and_ignored_directories() {
+    let dir = Path::new("test_scope_paths");
+    for sub in ["docs/guides", "node_modules/pkg", ".git", "src"] {
+        fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    for file in ["notes.txt", "secret-plan.txt", "docs/guides/setup.md", "readme.md", "src/main.rs", "node_modules/pkg/index.txt", ".git/config.txt"] {
+        fs::write(dir.join(file), "x").unwrap();
+    }
+
+    let scope = FileScope::new(&[root(dir, &["*.txt", "docs/**/*.md"], &["secret-*"])], &ScopeConfig::default()).unwrap();
+    let base = fs::canonicalize(dir).unwrap();
+    assert!(scope.allows_file(&base.join("notes.txt")));
+    assert!(scope.allows_file(&base.join("docs/guides/setup.md")));
+    assert!(!scope.allows_file(&base.join("readme.md")));
+    assert!(!scope.allows_file(&base.join("secret-plan.txt")));
+    assert!(!scope.allows_file(&base.join("node_modules/pkg/index.txt")));
+    assert!(!scope.allows_dir(&base.join(".git")));
+    assert!(scope.allows_dir(&base.join("src")));
+
+    let crawl_config = BaselineConfig { state_dir: dir.join("state"), ..Default::default() };
+    let mut crawl = BaselineCrawl::start_scoped(&base, &crawl_config, &scope);
+    let mut files = Vec::new();
+    while let Some(file) = crawl.next_file() {
+        files.push(file.strip_prefix(&base).unwrap().to_string_lossy().to_string());
+    }
+    assert_eq!(files, vec!["docs/guides/setup.md", "notes.txt"]);
+
+    let error = FileScope::new(&[root(dir, &["[unclosed"], &[])], &ScopeConfig::default()).err().unwrap();
+    assert!(error.to_string().contains("[unclosed"), "{}", error);
+    assert!(FileScope::new(&[WatchRoot::new(dir.join("missing"))], &ScopeConfig::default()).is_err());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_size_and_type_limits() {
+    let dir = Path::new("test_scope_content");
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("small.txt"), "hello").unwrap();
+    fs::write(dir.join("large.txt"), "x".repeat(2048)).unwrap();
+    fs::write(dir.join("report.pdf"), "%PDF-1.4\n%âãÏÓ\n").unwrap();
+    fs::write(dir.join("blob.bin"), [0u8, 1, 2, 3]).unwrap();
+
+    let config = ScopeConfig {
+        max_file_size: Some(1024),
+        allowed_mime_types: vec!["text/*".to_string(), "application/PDF".to_string()],
+        ..Default::default()
+    };
+    let scope = FileScope::new(&[WatchRoot::new(dir)], &config).unwrap();
+    assert_eq!(scope.check_content(&dir.join("small.txt")), Ok(()));
+    assert_eq!(scope.check_content(&dir.join("report.pdf")), Ok(()));
+    assert!(scope.check_content(&dir.join("large.txt")).unwrap_err().contains("1024 byte limit"));
+    assert_eq!(scope.check_content(&dir.join("blob.bin")), Err("type application/octet-stream is not allowed".to_string()));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_roots_and_scope_load_from_toml_and_yaml() {
+    let dir = Path::new("test_scope_config");
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("monitor.toml"), r#"
+[[roots]]
+path = "/home/shared"
+include = ["*.docx", "*.xlsx"]
+
+[[roots]]
+path = "/srv/exports"
+exclude = ["tmp/**"]
+
+[scope]
+ignored_dirs = [".git", "node_modules", "target"]
+max_file_size = 52428800
+allowed_mime_types = ["application/pdf", "text/*"]
+
+[workers]
+max_workers = 8
+"#).unwrap();
+    fs::write(dir.join("monitor.yaml"), "roots:\n  - path: /home/shared\n    include: ['*.docx', '*.xlsx']\n  - path: /srv/exports\n    exclude: ['tmp/**']\n").unwrap();
+
+    let toml = MonitorConfig::load(&dir.join("monitor.toml")).unwrap();
+    let yaml = MonitorConfig::load(&dir.join("monitor.yaml")).unwrap();
+    assert_eq!(toml.roots, yaml.roots);
+    assert_eq!(toml.roots[0].include, vec!["*.docx", "*.xlsx"]);
+    assert_eq!(toml.roots[1].exclude, vec!["tmp/**"]);
+    assert_eq!(toml.scope.max_file_size, Some(50 * 1024 * 1024));
+    assert_eq!(toml.workers.max_workers, 8);
+    // Left out, so the defaults apply
+    assert_eq!(yaml.scope.ignored_dirs, vec![".git", "node_modules"]);
+    assert_eq!(toml.context_chars, MonitorConfig::default().context_chars);
+
+    fs::write(dir.join("monitor.ini"), "").unwrap();
+    assert!(MonitorConfig::load(&dir.join("monitor.ini")).is_err());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_only_files_in_scope_are_scanned() {
+    let dir = Path::new("test_scope_monitor");
+    let shared = dir.join("shared");
+    let exports = dir.join("exports");
+    fs::create_dir_all(shared.join("node_modules")).unwrap();
+    fs::create_dir_all(&exports).unwrap();
+
+    let comm = Communication::new("test".to_string(), common::mock_endpoint());
+    let alerts = comm.alerts.clone();
+    let monitor = FileMonitor::new(comm).with_config(MonitorConfig {
+        roots: vec![root(&shared, &["*.txt"], &[]), root(&exports, &[], &["*.log"])],
+        scope: ScopeConfig { max_file_size: Some(1024), ..Default::default() },
+        ..Default::default()
+    });
+    let handle = tokio::spawn(async move {
+        monitor.start_monitoring_roots().await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let ssn = "SSN: 123-45-6789";
+    fs::write(shared.join("notes.txt"), ssn).unwrap();
+    fs::write(shared.join("notes.csv"), ssn).unwrap();
+    fs::write(shared.join("node_modules/dep.txt"), ssn).unwrap();
+    fs::write(exports.join("export.csv"), ssn).unwrap();
+    fs::write(exports.join("server.log"), ssn).unwrap();
+    fs::write(exports.join("huge.csv"), format!("{}\n{}", ssn, "x".repeat(2048))).unwrap();
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let mut scanned: Vec<String> = alerts
+        .lock()
+        .await
+        .iter()
+        .filter(|a| a.pattern_type == "US_SSN")
+        .map(|a| Path::new(&a.file_path).file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    scanned.sort();
+    assert_eq!(scanned, vec!["export.csv", "notes.txt"]);
+
+    handle.abort();
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,109 @@
+use file_monitor::config::{ArchiveLimits, ScannerConfig, TextLimits};
+use file_monitor::detectors::builtin_detectors;
+use file_monitor::scanner::spreadsheet::cell_reference;
+use file_monitor::{Confidence, ContentScanner, MatchLocation, ScannedContent};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use zip::write::FileOptions;
+
+#[test]
+fn test_cell_reference() {
+    assert_eq!(cell_reference(0, 0), "A1");
+    assert_eq!(cell_reference(6, 27), "AB7");
+    assert_eq!(cell_reference(9, 701), "ZZ10");
+}
+
+/// The SSN found in `contents`, located within its file.
+fn locate_ssn(contents: &[ScannedContent]) -> (MatchLocation, Confidence) {
+    let detectors = builtin_detectors();
+    let ssn = detectors.iter().find(|d| d.id() == "US_SSN").unwrap();
+    contents
+        .iter()
+        .find_map(|content| {
+            let finding = ssn.detect(&content.text).into_iter().find(|f| content.owns(f.start))?;
+            Some((MatchLocation::locate(content, &finding, 10), finding.confidence))
+        })
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_csv_and_tsv_cells_are_tagged() {
+    let dir = Path::new("test_csv");
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("staff.csv"), "name,ssn\nAlice,123-45-6789\n").unwrap();
+    fs::write(dir.join("staff.tsv"), "name\tssn\nBob\t234-56-7890\n").unwrap();
+
+    let scanner = ContentScanner::new();
+    let csv = scanner.scan(&dir.join("staff.csv")).await.unwrap().contents;
+    let tsv = scanner.scan(&dir.join("staff.tsv")).await.unwrap().contents;
+
+    // One piece per row, with the header after the row for context
+    assert_eq!(csv.len(), 2);
+    assert_eq!(&csv[1].text[..csv[1].owned_len], "Alice,123-45-6789");
+    let (location, confidence) = locate_ssn(&csv);
+    assert_eq!(location.origin.cell.as_deref(), Some("B2"));
+    assert_eq!(location.line, 2);
+    assert_eq!(confidence, Confidence::High);
+    let (location, _) = locate_ssn(&tsv);
+    assert_eq!(location.origin.cell.as_deref(), Some("B2"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_large_csv_is_streamed_outside_the_archive_budget() {
+    let dir = Path::new("test_csv_streamed");
+    fs::create_dir_all(dir).unwrap();
+    let mut csv = String::from("id,note,ssn\n");
+    for row in 1..200 {
+        csv.push_str(&format!("{},\"quoted, with a comma\",\n", row));
+    }
+    // Longer than a chunk, so handed over in pieces
+    csv.push_str(&format!("200,{},123-45-6789\n", "x".repeat(600)));
+    fs::write(dir.join("export.csv"), csv).unwrap();
+
+    let scanner = ContentScanner::with_config(ScannerConfig {
+        text: TextLimits { chunk_bytes: 256, ..Default::default() },
+        archive: ArchiveLimits { max_expanded_bytes: 1024, ..Default::default() },
+    });
+    let output = scanner.scan(&dir.join("export.csv")).await.unwrap();
+
+    assert!(output.violations.is_empty());
+    assert!(output.contents.len() > 201);
+    assert_eq!(&output.contents[7].text[..output.contents[7].owned_len], "7,quoted, with a comma");
+    let (location, confidence) = locate_ssn(&output.contents);
+    assert_eq!(location.origin.cell.as_deref(), Some("C201"));
+    assert_eq!(location.line, 201);
+    assert_eq!(confidence, Confidence::High);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_ods_cells_are_tagged_with_sheet() {
+    let dir = Path::new("test_ods");
+    fs::create_dir_all(dir).unwrap();
+    let path = dir.join("staff.ods");
+
+    let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+    zip.start_file("mimetype", FileOptions::default()).unwrap();
+    zip.write_all(b"application/vnd.oasis.opendocument.spreadsheet").unwrap();
+    zip.start_file("content.xml", FileOptions::default()).unwrap();
+    zip.write_all(br#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0">
+<office:body><office:spreadsheet><table:table table:name="Payroll">
+<table:table-row><table:table-cell office:value-type="string"><text:p>name</text:p></table:table-cell><table:table-cell office:value-type="string"><text:p>ssn</text:p></table:table-cell></table:table-row>
+<table:table-row><table:table-cell office:value-type="string"><text:p>Alice</text:p></table:table-cell><table:table-cell office:value-type="string"><text:p>123-45-6789</text:p></table:table-cell></table:table-row>
+</table:table></office:spreadsheet></office:body></office:document-content>"#).unwrap();
+    zip.finish().unwrap();
+
+    let contents = ContentScanner::new().scan(&path).await.unwrap().contents;
+    let (location, confidence) = locate_ssn(&contents);
+
+    assert_eq!(location.origin.sheet.as_deref(), Some("Payroll"));
+    assert_eq!(location.origin.cell.as_deref(), Some("B2"));
+    assert_eq!(confidence, Confidence::High);
+
+    fs::remove_dir_all(dir).unwrap();
+}
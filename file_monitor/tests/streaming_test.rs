@@ -0,0 +1,73 @@
+use file_monitor::config::{ScannerConfig, TextLimits};
+use file_monitor::detectors::builtin_detectors;
+use file_monitor::{ContentScanner, MatchLocation};
+use std::fs;
+use std::path::Path;
+
+fn scanner(chunk_bytes: usize, max_bytes_per_file: u64) -> ContentScanner {
+    ContentScanner::with_config(ScannerConfig {
+        text: TextLimits {
+            chunk_bytes,
+            max_bytes_per_file,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_matches_across_chunk_boundaries_are_reported_once() {
+    let dir = Path::new("test_streaming_chunks");
+    fs::create_dir_all(dir).unwrap();
+    let mut text = String::new();
+    for i in 0..200 {
+        text.push_str(&format!("line {} é SSN 123-45-{:04}\n", i, 1000 + i));
+    }
+    fs::write(dir.join("large.log"), &text).unwrap();
+
+    let ssn = builtin_detectors().into_iter().find(|d| d.id() == "US_SSN").unwrap();
+    // `\d` is Unicode-aware, so each digit may take up to four bytes
+    assert_eq!(ssn.max_match_len(), Some(38));
+
+    let mut chunks = 0;
+    let mut locations = Vec::new();
+    scanner(64, u64::MAX)
+        .scan_each(&dir.join("large.log"), 38, |content| {
+            chunks += 1;
+            // One chunk, the overlap and at most one held-back partial character
+            assert!(content.text.len() <= 64 + 32 + 3);
+            for finding in ssn.detect(&content.text) {
+                if content.owns(finding.start) {
+                    locations.push(MatchLocation::locate(&content, &finding, 0));
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+    assert!(chunks > 50);
+    assert_eq!(locations.len(), 200);
+    for (i, location) in locations.iter().enumerate() {
+        assert_eq!(location.line, i + 1);
+        assert_eq!(location.column, text.lines().nth(i).unwrap().chars().count() - 10);
+        assert_eq!(&text[location.offset..location.offset + 11], format!("123-45-{:04}", 1000 + i));
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_text_beyond_byte_budget_is_skipped() {
+    let dir = Path::new("test_streaming_budget");
+    fs::create_dir_all(dir).unwrap();
+    let text = format!("{}password=hunter2\n", "x".repeat(1000));
+    fs::write(dir.join("big.txt"), text).unwrap();
+
+    let output = scanner(128, 512).scan(&dir.join("big.txt")).await.unwrap();
+    let scanned: usize = output.contents.iter().map(|c| c.owned_len).sum();
+
+    assert_eq!(scanned, 512);
+    assert!(output.contents.iter().all(|c| !c.text.contains("password")));
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,39 @@
+use file_monitor::detectors::builtin_detectors;
+use file_monitor::validators::{card_brand, iban_valid, ssn_valid, CardBrand};
+
+#[test]
+fn test_card_brand_requires_luhn_and_known_iin() {
+    assert_eq!(card_brand("4111 1111 1111 1111"), Some(CardBrand::Visa));
+    assert_eq!(card_brand("5500-0000-0000-0004"), Some(CardBrand::Mastercard));
+    assert_eq!(card_brand("378282246310005"), Some(CardBrand::Amex));
+    assert_eq!(card_brand("4111 1111 1111 1112"), None);
+    assert_eq!(card_brand("1234567812345670"), None);
+}
+
+#[test]
+fn test_ssn_rejects_unassigned_ranges() {
+    assert!(ssn_valid("123-45-6789"));
+    assert!(!ssn_valid("000-12-3456"));
+    assert!(!ssn_valid("666-12-3456"));
+    assert!(!ssn_valid("912-12-3456"));
+    assert!(!ssn_valid("123-00-4567"));
+    assert!(!ssn_valid("123-45-0000"));
+    assert!(!ssn_valid("078-05-1120"));
+}
+
+#[test]
+fn test_iban_mod97() {
+    assert!(iban_valid("GB82 WEST 1234 5698 7654 32"));
+    assert!(iban_valid("DE89370400440532013000"));
+    assert!(!iban_valid("GB82 WEST 1234 5698 7654 33"));
+}
+
+#[test]
+fn test_builtin_card_detector_skips_order_ids() {
+    let detectors = builtin_detectors();
+    let card = detectors.iter().find(|d| d.id() == "CREDIT_CARD_NUMBER").unwrap();
+
+    let findings = card.detect("Order 1234567812345678, card 4111 1111 1111 1111");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].metadata.get("card_brand").map(String::as_str), Some("Visa"));
+}
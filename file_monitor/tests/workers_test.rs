@@ -0,0 +1,166 @@
+mod common;
+
+use file_monitor::config::{MonitorConfig, ScannerConfig, TextLimits};
+use file_monitor::detectors::builtin_detectors;
+use file_monitor::{
+    BaselineConfig, BaselineState, Category, Communication, Detector, FileMonitor, Finding, ScanQueue, Severity, WorkerConfig,
+};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+fn age(path: &Path, seconds: u64) {
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() - Duration::from_secs(seconds)).unwrap();
+}
+
+#[test]
+fn test_small_and_recent_files_first() {
+    let dir = Path::new("test_workers_queue");
+    fs::create_dir_all(dir).unwrap();
+    let big = dir.join("big.log");
+    let old = dir.join("old.txt");
+    let new = dir.join("new.txt");
+    fs::write(&big, vec![b'x'; 64 * 1024]).unwrap();
+    fs::write(&old, "old notes").unwrap();
+    fs::write(&new, "new notes").unwrap();
+    age(&old, 3600);
+
+    let mut queue = ScanQueue::new(3);
+    for path in [&big, &old, &new] {
+        assert!(queue.push(path.clone()));
+    }
+    // Already waiting, so not queued twice even though the queue is full
+    assert!(queue.push(old.clone()));
+    assert!(queue.is_full());
+    assert!(!queue.push(dir.join("more.txt")));
+
+    assert_eq!(queue.pop(), Some(new.clone()));
+    assert_eq!(queue.pop(), Some(old.clone()));
+    assert_eq!(queue.running(), 2);
+    assert_eq!(queue.space(), 2);
+
+    // A file changed again while being scanned waits for that scan to end
+    assert!(queue.push(new.clone()));
+    assert_eq!(queue.pop(), Some(big.clone()));
+    assert_eq!(queue.pop(), None);
+    queue.done(&new);
+    assert_eq!(queue.pop(), Some(new));
+    assert!(queue.is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_crawl_through_a_small_queue_scans_every_file() {
+    let dir = Path::new("test_workers_crawl");
+    let root = dir.join("root");
+    fs::create_dir_all(&root).unwrap();
+    for i in 0..20 {
+        fs::write(root.join(format!("{:02}.txt", i)), format!("record {}\nSSN: 123-45-{:04}", i, 6700 + i)).unwrap();
+    }
+    let baseline = BaselineConfig {
+        enabled: true,
+        files_per_second: 0,
+        state_dir: dir.join("state"),
+        ..Default::default()
+    };
+
+    let comm = Communication::new("test".to_string(), common::mock_endpoint());
+    let alerts = comm.alerts.clone();
+    let monitor = FileMonitor::new(comm).with_config(MonitorConfig {
+        baseline: baseline.clone(),
+        workers: WorkerConfig { max_workers: 2, max_queued: 2, ..Default::default() },
+        ..Default::default()
+    });
+    let watched = root.clone();
+    let handle = tokio::spawn(async move {
+        monitor.start_monitoring(&watched).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let mut scanned: Vec<String> = alerts
+        .lock()
+        .await
+        .iter()
+        .filter(|a| a.pattern_type == "US_SSN")
+        .map(|a| a.file_path.clone())
+        .collect();
+    scanned.sort();
+    scanned.dedup();
+    assert_eq!(scanned.len(), 20);
+    let state = BaselineState::load(&fs::canonicalize(&root).unwrap(), &baseline).unwrap();
+    assert_eq!(state.files_scanned, 20);
+    assert!(state.completed_at.is_some());
+
+    handle.abort();
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// Takes a fixed time per chunk, so a scan is slow however fast the machine.
+struct SlowDetector;
+
+impl Detector for SlowDetector {
+    fn id(&self) -> &str {
+        "SLOW"
+    }
+
+    fn name(&self) -> &str {
+        "Slow"
+    }
+
+    fn category(&self) -> Category {
+        Category::Custom
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Low
+    }
+
+    fn detect(&self, _content: &str) -> Vec<Finding> {
+        std::thread::sleep(Duration::from_millis(100));
+        Vec::new()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_timed_out_scans_are_abandoned() {
+    let dir = Path::new("test_workers_timeout");
+    let root = dir.join("root");
+    let _ = fs::remove_dir_all(dir);
+    fs::create_dir_all(&root).unwrap();
+    // Over a hundred chunks at 100ms each; the SSN is at the very end
+    let mut text = "lorem ipsum dolor sit amet 42 ".repeat(1_000);
+    text.push_str("SSN: 123-45-6789");
+    fs::write(root.join("huge.txt"), text).unwrap();
+    let baseline = BaselineConfig {
+        enabled: true,
+        files_per_second: 0,
+        state_dir: dir.join("state"),
+        ..Default::default()
+    };
+
+    let comm = Communication::new("test".to_string(), common::mock_endpoint());
+    let alerts = comm.alerts.clone();
+    let mut detectors = builtin_detectors();
+    detectors.push(Box::new(SlowDetector));
+    let monitor = FileMonitor::with_detectors(comm, detectors).with_config(MonitorConfig {
+        baseline: baseline.clone(),
+        scanner: ScannerConfig { text: TextLimits { chunk_bytes: 256, ..Default::default() }, ..Default::default() },
+        workers: WorkerConfig { timeout_secs: 1, ..Default::default() },
+        ..Default::default()
+    });
+    let watched = root.clone();
+    let handle = tokio::spawn(async move {
+        monitor.start_monitoring(&watched).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    assert!(alerts.lock().await.is_empty());
+    // The crawl still moves past the file
+    let state = BaselineState::load(&fs::canonicalize(&root).unwrap(), &baseline).unwrap();
+    assert!(state.completed_at.is_some());
+
+    handle.abort();
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use file_monitor::config::{MonitorConfig, ScannerConfig, TextLimits};
+use file_monitor::{Communication, FileMonitor, Severity, YaraRules};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const RULES: &str = r#"
+rule Board_Minutes : confidential finance {
+    meta:
+        description = "Board meeting minutes"
+        severity = "critical"
+        revision = 3
+    strings:
+        $title = "minutes of the board" nocase
+        $marker = "STRICTLY CONFIDENTIAL"
+    condition:
+        all of them
+}
+
+rule Elf_Binary {
+    strings:
+        $magic = { 7F 45 4C 46 }
+    condition:
+        $magic at 0
+}
+"#;
+
+#[test]
+fn test_matches_carry_rule_tags_and_metadata() {
+    let rules = YaraRules::from_source(RULES).unwrap();
+    assert_eq!(rules.rule_count(), 2);
+
+    let matches = rules.scan(b"STRICTLY CONFIDENTIAL\nMinutes of the Board, 3 March");
+    assert_eq!(matches.len(), 1);
+    let m = &matches[0];
+    assert_eq!(m.rule, "Board_Minutes");
+    assert_eq!(m.tags, vec!["confidential", "finance"]);
+    assert_eq!(m.metadata["description"], "Board meeting minutes");
+    assert_eq!(m.metadata["revision"], "3");
+    assert_eq!(m.severity(), Some(Severity::Critical));
+    assert_eq!(m.strings, vec!["$title", "$marker"]);
+    assert_eq!(m.match_count, 2);
+
+    let elf = rules.scan(b"\x7fELF\x02\x01\x01\x00");
+    assert_eq!(elf[0].rule, "Elf_Binary");
+    assert_eq!(elf[0].first_match, b"\x7fELF");
+    assert_eq!(elf[0].severity(), None);
+
+    assert!(rules.scan(b"minutes of the board only").is_empty());
+}
+
+#[test]
+fn test_matches_in_the_overlap_are_left_to_the_next_piece() {
+    let rules = YaraRules::from_source(RULES).unwrap();
+    let piece = b"Minutes of the Board\nSTRICTLY CONFIDENTIAL";
+    let owned_len = piece.len() - "STRICTLY CONFIDENTIAL".len();
+
+    let matches = rules.scan_piece(piece, owned_len);
+    assert_eq!(matches[0].strings, vec!["$title"]);
+    assert_eq!(matches[0].match_count, 1);
+    assert_eq!(rules.scan_piece(piece, 0)[0].match_count, 0);
+    assert_eq!(rules.scan(piece)[0].match_count, 2);
+}
+
+#[test]
+fn test_rule_directories_compile_with_a_namespace_per_file() {
+    let dir = Path::new("test_yara_rules");
+    fs::create_dir_all(dir.join("malware")).unwrap();
+    fs::write(dir.join("documents.yar"), RULES).unwrap();
+    // Same rule name in another file does not clash
+    fs::write(dir.join("malware/elf.yara"), "rule Elf_Binary { condition: uint32(0) == 0x464c457f }").unwrap();
+    fs::write(dir.join("README.md"), "not a rule").unwrap();
+
+    let rules = YaraRules::compile_dir(dir).unwrap();
+    assert_eq!(rules.rule_count(), 3);
+    let mut namespaces: Vec<String> = rules.scan(b"\x7fELF").into_iter().map(|m| m.namespace).collect();
+    namespaces.sort();
+    assert_eq!(namespaces, vec!["documents.yar", "malware/elf.yara"]);
+
+    fs::write(dir.join("broken.yar"), "rule Broken {\n    condition:\n        $missing\n}\n").unwrap();
+    let error = YaraRules::compile_dir(dir).err().unwrap().to_string();
+    assert!(error.contains("broken.yar:3:"), "{}", error);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_monitor_evaluates_raw_bytes_up_to_the_text_limit() {
+    let dir = Path::new("test_yara_monitor");
+    fs::create_dir_all(dir).unwrap();
+    let rules = YaraRules::from_source(RULES).unwrap();
+
+    let comm = Communication::new("test".to_string(), common::mock_endpoint());
+    let alerts = comm.alerts.clone();
+    let monitor = FileMonitor::new(comm).with_yara_rules(rules).with_config(MonitorConfig {
+        scanner: ScannerConfig { text: TextLimits { max_bytes_per_file: 1024, ..Default::default() }, ..Default::default() },
+        ..Default::default()
+    });
+    let watched = dir.to_path_buf();
+    let handle = tokio::spawn(async move {
+        monitor.start_monitoring(&watched).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let minutes = "STRICTLY CONFIDENTIAL\nMinutes of the Board";
+    fs::write(dir.join("early.txt"), minutes).unwrap();
+    fs::write(dir.join("late.txt"), format!("{}{}", " ".repeat(2048), minutes)).unwrap();
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let alerts = alerts.lock().await;
+    let matched: Vec<&str> = alerts
+        .iter()
+        .filter(|a| a.pattern_type == "YARA_MATCH")
+        .map(|a| Path::new(&a.file_path).file_name().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(matched, vec!["early.txt"]);
+    let early = alerts.iter().find(|a| a.pattern_type == "YARA_MATCH").unwrap();
+    assert_eq!(early.metadata["scanned"], "bytes, text");
+    drop(alerts);
+
+    handle.abort();
+    fs::remove_dir_all(dir).unwrap();
+}
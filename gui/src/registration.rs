@@ -90,7 +90,7 @@ impl RegistrationForm {
                 }
             };
 
-            let _ = tokio::spawn(task);
+            tokio::spawn(task);
         }
 
         completed
@@ -3,7 +3,7 @@ use tokio::sync::mpsc;
 use std::error::Error;
 use std::collections::HashMap;
 use pcap::{Device, Capture};
-use sysinfo::{System, SystemExt, CpuExt};
+use sysinfo::{System, SystemExt};
 use std::net::IpAddr;
 use pnet::packet::{ethernet, Packet};
 use pnet::packet::ipv4::Ipv4Packet;
@@ -43,6 +43,7 @@ pub enum AlertSeverity {
     Low,
 }
 
+#[allow(dead_code)]
 #[derive(Default)]
 struct NetworkStats {
     bandwidth_usage: f64,
@@ -53,6 +54,7 @@ struct NetworkStats {
     port_scan_attempts: HashMap<IpAddr, Vec<u16>>,
 }
 
+#[allow(dead_code)]
 struct ConnectionInfo {
     first_seen: DateTime<Utc>,
     last_seen: DateTime<Utc>,
@@ -61,6 +63,7 @@ struct ConnectionInfo {
     ports_accessed: Vec<u16>,
 }
 
+#[allow(dead_code)]
 pub struct NetworkMonitor {
     device_id: String,
     packet_capture: Capture<pcap::Active>,
@@ -72,6 +75,7 @@ pub struct NetworkMonitor {
     suspicious_ports: Vec<u16>,
 }
 
+#[allow(dead_code)]
 impl NetworkMonitor {
    pub fn new(device_id: String, alert_tx: mpsc::Sender<NetworkAlert>) -> Result<Self, Box<dyn Error>> {
        let devices = Device::list()?;
@@ -98,8 +102,7 @@ impl NetworkMonitor {
    async fn analyze_packet(&mut self, packet: &pcap::Packet<'_>) -> Option<NetworkAlert> {
        let eth_packet = ethernet::EthernetPacket::new(packet.data)?;
        
-       match eth_packet.get_ethertype() {
-           ethernet::EtherTypes::Ipv4 => {
+       if eth_packet.get_ethertype() == ethernet::EtherTypes::Ipv4 {
                if let Some(ip_packet) = Ipv4Packet::new(eth_packet.payload()) {
                    let source = IpAddr::V4(ip_packet.get_source());
                    let destination = IpAddr::V4(ip_packet.get_destination());
@@ -123,7 +126,7 @@ impl NetworkMonitor {
                    if let Some(tcp_packet) = TcpPacket::new(ip_packet.payload()) {
                        let entry = self.stats.port_scan_attempts
                            .entry(source)
-                           .or_insert_with(Vec::new);
+                           .or_default();
                        
                        entry.push(tcp_packet.get_destination());
                        
@@ -160,8 +163,6 @@ impl NetworkMonitor {
                 self.update_bandwidth_stats(ip_packet.payload().len() as u64);
 
                }
-            }
-            _ => {}
         }
 
         None